@@ -31,6 +31,7 @@ fn main() -> io::Result<()> {
         // * kernel_poll_only
         // * io_poll
         iouring: IoUringConfiguration::interrupt_driven(1 << 11),
+        ..NucleiConfig::default()
     };
     let _ = Proactor::with_config(nuclei_config);
 
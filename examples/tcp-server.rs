@@ -3,7 +3,8 @@ use nuclei::*;
 use std::net::{TcpListener, TcpStream};
 
 async fn echo(stream: Handle<TcpStream>) -> io::Result<()> {
-    io::copy(&stream, &mut &stream).await?;
+    // Bytes go back to the client through a kernel pipe, without being copied to userspace.
+    while nuclei::io::splice(&stream, &stream, 1 << 16).await? > 0 {}
     Ok(())
 }
 
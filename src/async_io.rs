@@ -1,5 +1,6 @@
 use super::handle::Handle;
 use super::submission_handler::SubmissionHandler;
+use crate::Proactor;
 use futures::io::{AsyncRead, AsyncWrite, SeekFrom};
 
 use std::{fs::File, pin::Pin, task::Context, task::Poll};
@@ -88,12 +89,11 @@ impl AsyncRead for &Handle<File> {
         let buf = buf.as_mut_ptr();
 
         let completion_dispatcher = async move {
-            let file = unsafe { File::from_raw_fd(raw_fd) };
+            // Borrowed descriptor, it is closed by the handle even if the operation is dropped midway.
+            let file = ManuallyDrop::new(unsafe { File::from_raw_fd(raw_fd) });
 
             let buf = unsafe { std::slice::from_raw_parts_mut(buf, buf_len) };
-            let size = Processor::processor_read_file(&file, buf).await?;
-
-            let _ = ManuallyDrop::new(file);
+            let size = Processor::processor_read_file(&*file, buf).await?;
             Ok(size)
         };
 
@@ -113,12 +113,10 @@ impl AsyncWrite for &Handle<File> {
         let buf = buf.as_ptr();
 
         let completion_dispatcher = async move {
-            let file = unsafe { File::from_raw_fd(raw_fd) };
+            let file = ManuallyDrop::new(unsafe { File::from_raw_fd(raw_fd) });
 
             let buf = unsafe { std::slice::from_raw_parts(buf, buf_len) };
-            let size = Processor::processor_write_file(&file, buf).await?;
-
-            let _ = ManuallyDrop::new(file);
+            let size = Processor::processor_write_file(&*file, buf).await?;
             Ok(size)
         };
 
@@ -144,10 +142,8 @@ impl AsyncSeek for Handle<File> {
         let raw_fd = self.as_raw_fd();
 
         let completion_dispatcher = async move {
-            let file = unsafe { File::from_raw_fd(raw_fd) };
-            let newpos = Processor::processor_seek_file(&file, pos).await?;
-
-            let _ = ManuallyDrop::new(file);
+            let file = ManuallyDrop::new(unsafe { File::from_raw_fd(raw_fd) });
+            let newpos = Processor::processor_seek_file(&*file, pos).await?;
             Ok(newpos)
         };
 
@@ -187,8 +183,53 @@ impl Handle<File> {
             store_file: Some(StoreFile::new(fd as _)),
             read: Arc::new(TTas::new(None)),
             write: Arc::new(TTas::new(None)),
+            read_timeout: None,
+            write_timeout: None,
+            proactor: Proactor::current(),
+            direct: None,
         })
     }
+
+    /// Copies the data into the buffer of the handle with `fill`, and writes it to the file.
+    fn poll_write_buffered(
+        &mut self,
+        cx: &mut Context<'_>,
+        fill: impl FnOnce(&mut [u8]) -> io::Result<usize>,
+    ) -> Poll<io::Result<usize>> {
+        let write = self.write.clone();
+        let proactor = self.proactor.clone();
+
+        if let Some(store_file) = self.store_file.as_mut() {
+            let fd: RawFd = store_file.receive_fd();
+            let (bufp, pos) = store_file.bufpair();
+
+            // Data of a pending write stays buffered, it isn't copied again on the next poll.
+            let data = futures::ready!(bufp.fill_buf(|buf| Poll::Ready(fill(buf))))?;
+
+            let offset = *pos;
+            let data_len = data.len();
+            let data = data.as_ptr();
+
+            let completion_dispatcher = async move {
+                let data = unsafe { std::slice::from_raw_parts(data, data_len) };
+                Processor::processor_write_file(&fd, data, offset).await
+            };
+
+            let n = futures::ready!(SubmissionHandler::<Self>::handle_op(
+                write,
+                &proactor,
+                cx,
+                completion_dispatcher
+            ))?;
+            *pos += n;
+
+            bufp.clear();
+
+            Poll::Ready(Ok(n))
+        } else {
+            Poll::Ready(Ok(0))
+        }
+    }
 }
 
 #[cfg(all(feature = "iouring", target_os = "linux"))]
@@ -205,32 +246,15 @@ impl AsyncRead for Handle<File> {
     }
 
     fn poll_read_vectored(
-        self: Pin<&mut Self>,
+        mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         bufs: &mut [IoSliceMut<'_>],
     ) -> Poll<io::Result<usize>> {
-        let store = &mut self.get_mut().store_file;
-
-        if let Some(store_file) = store.as_mut() {
-            let fd: RawFd = store_file.receive_fd();
-            let op_state = store_file.op_state();
-            let (_, pos) = store_file.bufpair();
-
-            let fut = Processor::processor_read_vectored(&fd, bufs);
-            futures::pin_mut!(fut);
-
-            loop {
-                match fut.as_mut().poll(cx)? {
-                    Poll::Ready(n) => {
-                        *pos += n;
-                        break Poll::Ready(Ok(n));
-                    }
-                    _ => {}
-                }
-            }
-        } else {
-            Poll::Ready(Ok(0))
-        }
+        // Read goes through the buffer of the handle, slices might differ between polls.
+        let mut inner = futures::ready!(self.as_mut().poll_fill_buf(cx))?;
+        let len = io::Read::read_vectored(&mut inner, bufs)?;
+        self.consume(len);
+        Poll::Ready(Ok(len))
     }
 }
 
@@ -240,26 +264,33 @@ const NON_READ: &[u8] = &[];
 #[cfg(all(feature = "iouring", target_os = "linux"))]
 impl AsyncBufRead for Handle<File> {
     fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
-        let store = &mut self.get_mut().store_file;
+        let this = self.get_mut();
+        let read = this.read.clone();
+        let proactor = this.proactor.clone();
+        let timeout = this.read_timeout;
 
-        if let Some(store_file) = store.as_mut() {
+        if let Some(store_file) = this.store_file.as_mut() {
             let fd: RawFd = store_file.receive_fd();
-            let op_state = store_file.op_state();
             let (bufp, pos) = store_file.bufpair();
 
             bufp.fill_buf(|buf| {
-                let fut = Processor::processor_read_file(&fd, buf, *pos);
-                futures::pin_mut!(fut);
-
-                loop {
-                    match fut.as_mut().poll(cx)? {
-                        Poll::Ready(n) => {
-                            *pos += n;
-                            break Poll::Ready(Ok(n));
-                        }
-                        _ => {}
-                    }
-                }
+                let offset = *pos;
+                let buf_len = buf.len();
+                let buf = buf.as_mut_ptr();
+
+                let completion_dispatcher = async move {
+                    let buf = unsafe { std::slice::from_raw_parts_mut(buf, buf_len) };
+                    Processor::processor_read_file(&fd, buf, offset, timeout).await
+                };
+
+                let n = futures::ready!(SubmissionHandler::<Self>::handle_op(
+                    read,
+                    &proactor,
+                    cx,
+                    completion_dispatcher
+                ))?;
+                *pos += n;
+                Poll::Ready(Ok(n))
             })
         } else {
             Poll::Ready(Ok(NON_READ))
@@ -279,39 +310,8 @@ impl AsyncWrite for Handle<File> {
         cx: &mut Context<'_>,
         bufslice: &[u8],
     ) -> Poll<io::Result<usize>> {
-        let store = &mut self.get_mut().store_file;
-
-        if let Some(store_file) = store.as_mut() {
-            let fd: RawFd = store_file.receive_fd();
-            let op_state = store_file.op_state();
-            let (bufp, pos) = store_file.bufpair();
-
-            let data = futures::ready!(bufp.fill_buf(|mut buf| {
-                Poll::Ready(Ok(io::Write::write(&mut buf, bufslice).unwrap()))
-            }))
-            .unwrap();
-
-            let res = {
-                let fut = Processor::processor_write_file(&fd, data, *pos);
-                futures::pin_mut!(fut);
-
-                loop {
-                    match fut.as_mut().poll(cx)? {
-                        Poll::Ready(n) => {
-                            *pos += n;
-                            break Poll::Ready(Ok(n));
-                        }
-                        _ => {}
-                    }
-                }
-            };
-
-            bufp.clear();
-
-            res
-        } else {
-            Poll::Ready(Ok(0))
-        }
+        self.get_mut()
+            .poll_write_buffered(cx, |mut buf| io::Write::write(&mut buf, bufslice))
     }
 
     fn poll_write_vectored(
@@ -319,28 +319,8 @@ impl AsyncWrite for Handle<File> {
         cx: &mut Context<'_>,
         bufs: &[IoSlice<'_>],
     ) -> Poll<io::Result<usize>> {
-        let store = &mut self.get_mut().store_file;
-
-        if let Some(store_file) = store.as_mut() {
-            let fd: RawFd = store_file.receive_fd();
-            let op_state = store_file.op_state();
-            let (_, pos) = store_file.bufpair();
-
-            let fut = Processor::processor_write_vectored(&fd, bufs);
-            futures::pin_mut!(fut);
-
-            loop {
-                match fut.as_mut().poll(cx)? {
-                    Poll::Ready(n) => {
-                        *pos += n;
-                        break Poll::Ready(Ok(n));
-                    }
-                    _ => {}
-                }
-            }
-        } else {
-            Poll::Ready(Ok(0))
-        }
+        self.get_mut()
+            .poll_write_buffered(cx, |mut buf| io::Write::write_vectored(&mut buf, bufs))
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
@@ -349,21 +329,22 @@ impl AsyncWrite for Handle<File> {
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        let store = &mut self.get_mut().store_file;
+        let this = self.get_mut();
+        let write = this.write.clone();
+        let proactor = this.proactor.clone();
 
-        if let Some(store_file) = store.as_mut() {
+        if let Some(store_file) = this.store_file.as_mut() {
             let fd: RawFd = store_file.receive_fd();
-            let op_state = store_file.op_state();
 
-            let fut = Processor::processor_close_file(&fd);
-            futures::pin_mut!(fut);
+            let completion_dispatcher = async move { Processor::processor_close_file(&fd).await };
 
-            loop {
-                match fut.as_mut().poll(cx)? {
-                    Poll::Ready(_) => break Poll::Ready(Ok(())),
-                    _ => {}
-                }
-            }
+            futures::ready!(SubmissionHandler::<Self>::handle_op(
+                write,
+                &proactor,
+                cx,
+                completion_dispatcher
+            ))?;
+            Poll::Ready(Ok(()))
         } else {
             Poll::Ready(Ok(()))
         }
@@ -377,7 +358,10 @@ impl AsyncSeek for Handle<File> {
         cx: &mut Context<'_>,
         pos: SeekFrom,
     ) -> Poll<io::Result<u64>> {
-        let store = &mut self.get_mut().store_file.as_mut().unwrap();
+        let this = self.get_mut();
+        let read = this.read.clone();
+        let proactor = this.proactor.clone();
+        let store = this.store_file.as_mut().unwrap();
 
         let (cursor, offset) = match pos {
             io::SeekFrom::Start(n) => {
@@ -386,9 +370,14 @@ impl AsyncSeek for Handle<File> {
             }
             io::SeekFrom::Current(n) => (*store.pos(), n),
             io::SeekFrom::End(n) => {
-                let fut = store.poll_file_size();
-                futures::pin_mut!(fut);
-                (futures::ready!(fut.as_mut().poll(cx))?, n)
+                let completion_dispatcher = store.poll_file_size();
+                let size = futures::ready!(SubmissionHandler::<Self>::handle_op(
+                    read,
+                    &proactor,
+                    cx,
+                    completion_dispatcher
+                ))?;
+                (size, n)
             }
         };
         let valid_seek = if offset.is_negative() {
@@ -425,16 +414,15 @@ impl AsyncRead for &Handle<TcpStream> {
         buf: &mut [u8],
     ) -> Poll<io::Result<usize>> {
         let raw_fd = self.as_raw_fd();
+        let timeout = self.read_timeout;
         let buf_len = buf.len();
         let buf = buf.as_mut_ptr();
 
         let completion_dispatcher = async move {
-            let sock = unsafe { TcpStream::from_raw_fd(raw_fd) };
+            let sock = ManuallyDrop::new(unsafe { TcpStream::from_raw_fd(raw_fd) });
 
             let buf = unsafe { std::slice::from_raw_parts_mut(buf, buf_len) };
-            let size = Processor::processor_recv(&sock, buf).await?;
-
-            let _ = ManuallyDrop::new(sock);
+            let size = Processor::processor_recv(&*sock, buf, timeout).await?;
             Ok(size)
         };
 
@@ -446,16 +434,15 @@ impl AsyncRead for &Handle<TcpStream> {
 impl AsyncWrite for &Handle<TcpStream> {
     fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
         let raw_fd = self.as_raw_fd();
+        let timeout = self.write_timeout;
         let buf_len = buf.len();
         let buf = buf.as_ptr();
 
         let completion_dispatcher = async move {
-            let sock = unsafe { TcpStream::from_raw_fd(raw_fd) };
+            let sock = ManuallyDrop::new(unsafe { TcpStream::from_raw_fd(raw_fd) });
 
             let buf = unsafe { std::slice::from_raw_parts(buf, buf_len) };
-            let size = Processor::processor_send(&sock, buf).await?;
-
-            let _ = ManuallyDrop::new(sock);
+            let size = Processor::processor_send(&*sock, buf, timeout).await?;
             Ok(size)
         };
 
@@ -501,16 +488,15 @@ impl AsyncRead for &Handle<UnixStream> {
         buf: &mut [u8],
     ) -> Poll<io::Result<usize>> {
         let raw_fd = self.as_raw_fd();
+        let timeout = self.read_timeout;
         let buf_len = buf.len();
         let buf = buf.as_mut_ptr();
 
         let completion_dispatcher = async move {
-            let sock = unsafe { UnixStream::from_raw_fd(raw_fd) };
+            let sock = ManuallyDrop::new(unsafe { UnixStream::from_raw_fd(raw_fd) });
 
             let buf = unsafe { std::slice::from_raw_parts_mut(buf, buf_len) };
-            let size = Processor::processor_recv(&sock, buf).await?;
-
-            let _ = ManuallyDrop::new(sock);
+            let size = Processor::processor_recv(&*sock, buf, timeout).await?;
             Ok(size)
         };
 
@@ -522,16 +508,15 @@ impl AsyncRead for &Handle<UnixStream> {
 impl AsyncWrite for &Handle<UnixStream> {
     fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
         let raw_fd = self.as_raw_fd();
+        let timeout = self.write_timeout;
         let buf_len = buf.len();
         let buf = buf.as_ptr();
 
         let completion_dispatcher = async move {
-            let sock = unsafe { UnixStream::from_raw_fd(raw_fd) };
+            let sock = ManuallyDrop::new(unsafe { UnixStream::from_raw_fd(raw_fd) });
 
             let buf = unsafe { std::slice::from_raw_parts(buf, buf_len) };
-            let size = Processor::processor_send(&sock, buf).await?;
-
-            let _ = ManuallyDrop::new(sock);
+            let size = Processor::processor_send(&*sock, buf, timeout).await?;
             Ok(size)
         };
 
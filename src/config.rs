@@ -1,9 +1,44 @@
+use std::fmt;
+use std::os::unix::io::RawFd;
+use std::sync::Arc;
+
+pub use crate::sys::IoBackend;
+use crate::EventHook;
+
+/// Loading the configuration from environment variables and files.
+mod loader;
+
 ///
 /// Nuclei's proactor configuration.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct NucleiConfig {
+    /// IO backend that proactor is going to be built on.
+    ///
+    /// On Linux, both [IoBackend::IoUring] and [IoBackend::Epoll] are available when `iouring` feature is enabled.
+    /// Selecting a backend that can't be initialized fails the proactor initialization.
+    ///
+    /// **[default]**: If [None] then io_uring is probed at startup and epoll is used as fallback
+    /// when io_uring is unsupported or disabled (seccomp, `io_uring_disabled` sysctl).
+    pub backend: Option<IoBackend>,
     /// **IO_URING Configuration** allows you to configure [io_uring](https://unixism.net/loti/what_is_io_uring.html) backend.
     pub iouring: IoUringConfiguration,
+    /// **Epoll Configuration** allows you to configure [epoll](https://man7.org/linux/man-pages/man7/epoll.7.html) backend.
+    pub epoll: EpollConfiguration,
+    /// Hook that is called with the events of every operation, to plug in tracing or auditing.
+    ///
+    /// **[default]**: [None]
+    pub event_hook: Option<Arc<dyn EventHook>>,
+}
+
+impl fmt::Debug for NucleiConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NucleiConfig")
+            .field("backend", &self.backend)
+            .field("iouring", &self.iouring)
+            .field("epoll", &self.epoll)
+            .field("event_hook", &self.event_hook.as_ref().map(|_| ".."))
+            .finish()
+    }
 }
 
 /// **IO_URING Configuration**
@@ -61,7 +96,7 @@ pub struct IoUringConfiguration {
     /// Mind that, using this increase pressure on CPUs from userspace side. By default, nuclei reaps the CQ with
     /// aggressive wait. This is double polling approach for nuclei where Kernel gets submissions by itself (SQPOLL),
     /// processes it and puts completions to completion queue and we immediately pick up without latency
-    /// (aggressive_poll).
+    /// (aggressive_poll). A thread that parks in [drive](crate::drive) still blocks while there are no completions.
     ///
     /// **[default]**: `true`.
     pub aggressive_poll: bool,
@@ -72,6 +107,88 @@ pub struct IoUringConfiguration {
     ///
     /// **[default]**: `false`.
     pub iopoll_enabled: bool,
+
+    /// Policy that is applied when the submission queue is full.
+    ///
+    /// Before the policy kicks in, nuclei submits the queued entries to the kernel and reaps the
+    /// completion queue to free submission slots. Occurrences are counted and reported by
+    /// [Proactor::sq_full_events](crate::Proactor::sq_full_events).
+    ///
+    /// **[default]**: [SubmissionBackpressure::Wait].
+    pub backpressure: SubmissionBackpressure,
+
+    /// Batching policy of submissions, trades submission latency for fewer `io_uring_enter` calls.
+    ///
+    /// **[default]**: [SubmissionBatching::Immediate].
+    pub batching: SubmissionBatching,
+
+    /// Entries in the completion queue, it can't be less than `queue_len`.
+    /// A larger completion queue absorbs bursts of completions, like the ones of multishot operations.
+    ///
+    /// **[default]**: If [None] then the kernel sizes it as twice the `queue_len`.
+    pub cq_len: Option<u32>,
+
+    /// Clamp `queue_len` and `cq_len` to the maximum the kernel allows, instead of failing
+    /// the ring setup when they are larger.
+    ///
+    /// **[default]**: `false`.
+    pub clamp: bool,
+
+    /// Hint the kernel that only the thread that builds the proactor submits to the ring (6.0+).
+    ///
+    /// Only usable when the proactor is driven by the thread that builds it, like the workers of
+    /// the [runtime](crate::runtime), submissions from other threads fail with `EEXIST`.
+    ///
+    /// **[default]**: `false`.
+    pub single_issuer: bool,
+
+    /// Run the completion task work cooperatively, without interrupting the submitting thread (5.19+).
+    /// Reduces the overhead of completions for threads that enter the kernel often anyway.
+    ///
+    /// **[default]**: `false`.
+    pub coop_taskrun: bool,
+
+    /// Defer the completion task work until the submitting thread waits for completions (6.1+).
+    ///
+    /// Requires `single_issuer`, and can't be combined with SQPOLL.
+    ///
+    /// **[default]**: `false`.
+    pub defer_taskrun: bool,
+
+    /// CPU that the SQPOLL kernel thread is pinned to, it requires `sqpoll_wake_interval`.
+    ///
+    /// **[default]**: If [None] then the kernel thread is free to migrate between CPUs.
+    pub sqpoll_cpu: Option<u32>,
+
+    /// Keep submitting the batch when one of the submissions fails to be issued (5.18+),
+    /// instead of stopping at the failing one.
+    ///
+    /// **[default]**: `false`.
+    pub submit_all: bool,
+
+    /// File descriptor of another io_uring, see [Proactor::ring_fd](crate::Proactor::ring_fd),
+    /// whose kernel worker pool is shared with this ring instead of creating a new one.
+    ///
+    /// **[default]**: If [None] then the ring gets its own worker pool.
+    pub attach_wq: Option<RawFd>,
+
+    /// Buffers in the provided buffer ring that multishot receives pick from (5.19+),
+    /// it must be a power of two up to `32768`. The ring is registered on the first multishot receive.
+    ///
+    /// **[default]**: `512`.
+    pub recv_buf_ring_len: u32,
+
+    /// Size of each buffer in the provided buffer ring, it bounds the chunks of multishot receives.
+    ///
+    /// **[default]**: `4096`.
+    pub recv_buf_size: u32,
+
+    /// Slots in the registered file table that direct descriptors are installed to, see
+    /// [Handle::register_direct](crate::Handle::register_direct) and [Direct](crate::Direct).
+    /// The sparse table is registered on the first direct descriptor, `0` disables them.
+    ///
+    /// **[default]**: `1024`.
+    pub file_table_len: u32,
     // XXX: `redrive_kthread_wake` = bool, syncs queue changes so kernel threads got awakened. increased cpu usage.
 }
 
@@ -91,6 +208,19 @@ impl IoUringConfiguration {
             per_numa_unbounded_worker_count: None,
             aggressive_poll: false,
             iopoll_enabled: false,
+            backpressure: SubmissionBackpressure::default(),
+            batching: SubmissionBatching::Immediate,
+            cq_len: None,
+            clamp: false,
+            single_issuer: false,
+            coop_taskrun: false,
+            defer_taskrun: false,
+            sqpoll_cpu: None,
+            submit_all: false,
+            attach_wq: None,
+            recv_buf_ring_len: 1 << 9,
+            recv_buf_size: 1 << 12,
+            file_table_len: 1 << 10,
         }
     }
 
@@ -138,6 +268,100 @@ impl Default for IoUringConfiguration {
             per_numa_unbounded_worker_count: Some(1 << 9),
             aggressive_poll: true,
             iopoll_enabled: false,
+            backpressure: SubmissionBackpressure::default(),
+            batching: SubmissionBatching::Immediate,
+            cq_len: None,
+            clamp: false,
+            single_issuer: false,
+            coop_taskrun: false,
+            defer_taskrun: false,
+            sqpoll_cpu: None,
+            submit_all: false,
+            attach_wq: None,
+            recv_buf_ring_len: 1 << 9,
+            recv_buf_size: 1 << 12,
+            file_table_len: 1 << 10,
         }
     }
 }
+
+///
+/// Backpressure policy of the IO_URING submission queue.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SubmissionBackpressure {
+    /// Block the submitting thread, submitting and reaping until a submission slot is available.
+    #[default]
+    Wait,
+    /// Fail the submission with [std::io::ErrorKind::WouldBlock].
+    WouldBlock,
+    /// Park the submission in an in-memory overflow queue, the submitting future stays pending
+    /// until the overflow is flushed to the submission queue and the operation completes.
+    Overflow,
+}
+
+///
+/// Batching policy of the IO_URING submissions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubmissionBatching {
+    /// Enter the kernel for every submission.
+    /// With SQPOLL, kernel is only entered to wake up the idle kernel thread.
+    Immediate,
+    /// Queue submissions and enter the kernel once given amount of them is pending,
+    /// or at the next driver tick, whichever comes first.
+    ///
+    /// With SQPOLL, queued submissions are published to the kernel thread right away,
+    /// and waking it up is deferred the same way.
+    Threshold(u32),
+}
+
+/// **Epoll Configuration**
+#[derive(Clone, Debug, Default)]
+pub struct EpollConfiguration {
+    /// Events that are read from epoll in a single `epoll_wait`.
+    ///
+    /// **[default]**: If [None] then the `max_event_size` that is passed to
+    /// [Proactor::wait](crate::Proactor::wait) is used.
+    pub max_events: Option<usize>,
+
+    /// Trigger mode of the registered file descriptors.
+    ///
+    /// **[default]**: [EpollTrigger::Edge].
+    pub trigger: EpollTrigger,
+
+    /// Register file descriptors with `EPOLLEXCLUSIVE` (4.5+), so a listener that is shared between
+    /// proactors wakes only one of them per connection, instead of all of them.
+    ///
+    /// Exclusive registrations can't be modified, so the interest changes of a file descriptor
+    /// re-add it instead, and `EPOLLPRI` isn't watched.
+    ///
+    /// **[default]**: `false`.
+    pub exclusive: bool,
+
+    /// Busy polling of the network devices in `epoll_wait` (6.9+), trades CPU for latency.
+    ///
+    /// **[default]**: If [None] then the system-wide `net.core.busy_poll` setting is used.
+    pub busy_poll: Option<EpollBusyPoll>,
+}
+
+///
+/// Trigger mode of the epoll registrations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EpollTrigger {
+    /// Events are reported when the readiness changes, with `EPOLLET`.
+    #[default]
+    Edge,
+    /// Events are reported as long as the file descriptor is ready.
+    Level,
+}
+
+///
+/// Busy polling parameters of an epoll instance, set with `EPIOCSPARAMS`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EpollBusyPoll {
+    /// Time to busy poll for, in microseconds.
+    pub usecs: u32,
+    /// Packets to process per busy poll, more than `64` requires `CAP_NET_ADMIN`.
+    pub budget: u16,
+    /// Prefer busy polling over the softirq processing of the packets.
+    pub prefer: bool,
+}
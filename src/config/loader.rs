@@ -0,0 +1,328 @@
+use std::path::Path;
+use std::{env, fs, io};
+
+use super::{EpollBusyPoll, EpollTrigger, IoBackend, IoUringConfiguration, NucleiConfig};
+use super::{SubmissionBackpressure, SubmissionBatching};
+
+/// Prefix of the environment variables, they are the upper-cased keys after it.
+const ENV_PREFIX: &str = "NUCLEI_";
+
+/// Keys of the configuration, in the files and after [ENV_PREFIX] in the environment.
+const KEYS: &[&str] = &[
+    "preset",
+    "backend",
+    "queue_len",
+    "sqpoll_ms",
+    "sqpoll_cpu",
+    "bounded_workers",
+    "unbounded_workers",
+    "aggressive_poll",
+    "iopoll",
+    "backpressure",
+    "batching",
+    "cq_len",
+    "clamp",
+    "single_issuer",
+    "coop_taskrun",
+    "defer_taskrun",
+    "submit_all",
+    "recv_buf_ring_len",
+    "recv_buf_size",
+    "file_table_len",
+    "epoll_max_events",
+    "epoll_trigger",
+    "epoll_exclusive",
+    "epoll_busy_poll_usecs",
+    "epoll_busy_poll_budget",
+    "epoll_prefer_busy_poll",
+];
+
+/// Packets processed per busy poll when the budget isn't given, same as the kernel's default.
+const BUSY_POLL_BUDGET: u16 = 8;
+
+/// Sections that the keys can be grouped under in the files.
+const SECTIONS: &[&str] = &["nuclei", "iouring", "epoll"];
+
+/// Value of a key, along with where it is set for the error messages.
+struct Setting {
+    key: &'static str,
+    value: String,
+    origin: String,
+}
+
+impl Setting {
+    fn invalid(&self, expected: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "nuclei: invalid value `{}` for {}, expected {}",
+                self.value, self.origin, expected
+            ),
+        )
+    }
+
+    fn u32(&self) -> io::Result<u32> {
+        self.value
+            .parse()
+            .map_err(|_| self.invalid("an unsigned integer"))
+    }
+
+    /// Unsigned integer, or `none` to leave it unset.
+    fn optional_u32(&self) -> io::Result<Option<u32>> {
+        match self.value.to_ascii_lowercase().as_str() {
+            "none" | "off" => Ok(None),
+            _ => self
+                .value
+                .parse()
+                .map(Some)
+                .map_err(|_| self.invalid("an unsigned integer or `none`")),
+        }
+    }
+
+    fn bool(&self) -> io::Result<bool> {
+        match self.value.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Ok(true),
+            "false" | "no" | "off" | "0" => Ok(false),
+            _ => Err(self.invalid("`true` or `false`")),
+        }
+    }
+}
+
+impl NucleiConfig {
+    /// Builds the configuration from the `NUCLEI_*` environment variables, the rest is left to defaults.
+    ///
+    /// Variables are the upper-cased keys of [NucleiConfig::parse] with the `NUCLEI_` prefix,
+    /// like `NUCLEI_QUEUE_LEN`, `NUCLEI_SQPOLL_MS`, `NUCLEI_BACKEND` and `NUCLEI_AGGRESSIVE_POLL`.
+    /// Unknown `NUCLEI_*` variables are rejected, so typos don't go unnoticed.
+    pub fn from_env() -> io::Result<NucleiConfig> {
+        let mut settings = Vec::new();
+        for (name, value) in env::vars() {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let key = KEYS
+                .iter()
+                .find(|k| k.eq_ignore_ascii_case(key))
+                .ok_or_else(|| {
+                    invalid(format!("nuclei: unknown environment variable `{}`", name))
+                })?;
+
+            settings.push(Setting {
+                key,
+                value: value.trim().to_owned(),
+                origin: format!("`{}`", name),
+            });
+        }
+
+        build(settings)
+    }
+
+    /// Reads the configuration file at `path`, see [NucleiConfig::parse] for its format.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<NucleiConfig> {
+        NucleiConfig::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parses a configuration of `key = value` lines, the rest is left to defaults.
+    ///
+    /// Keys can be grouped under `[nuclei]`, `[iouring]` and `[epoll]` sections, values can be quoted and
+    /// `#` starts a comment, so simple TOML files are accepted as well:
+    ///
+    /// ```toml
+    /// backend = "iouring"       # `iouring`, `epoll` or `auto`
+    ///
+    /// [iouring]
+    /// preset = "interrupt_driven" # `interrupt_driven`, `low_latency_driven`, `kernel_poll_only` or `io_poll`
+    /// queue_len = 4096
+    /// sqpoll_ms = "none"        # SQPOLL wake interval, `none` disables SQPOLL
+    /// sqpoll_cpu = "none"
+    /// bounded_workers = 256
+    /// unbounded_workers = 512
+    /// aggressive_poll = false
+    /// iopoll = false
+    /// backpressure = "wait"     # `wait`, `would_block` or `overflow`
+    /// batching = 32             # `immediate` or the submission threshold
+    /// cq_len = 8192
+    /// clamp = false
+    /// single_issuer = false
+    /// coop_taskrun = false
+    /// defer_taskrun = false
+    /// submit_all = false
+    /// recv_buf_ring_len = 512   # buffers of multishot receives, a power of two
+    /// recv_buf_size = 4096
+    /// file_table_len = 1024     # slots of the direct descriptors
+    ///
+    /// [epoll]
+    /// epoll_max_events = 1024
+    /// epoll_trigger = "edge"    # `edge` or `level`
+    /// epoll_exclusive = false
+    /// epoll_busy_poll_usecs = 50 # enables busy polling
+    /// epoll_busy_poll_budget = 8
+    /// epoll_prefer_busy_poll = false
+    /// ```
+    ///
+    /// The preset is applied first, with the `queue_len` if it is given, and the rest of the keys
+    /// override it.
+    pub fn parse(s: &str) -> io::Result<NucleiConfig> {
+        let mut settings = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if !SECTIONS.contains(&section.trim()) {
+                    return Err(invalid(format!(
+                        "nuclei: unknown section `{}` at line {}",
+                        section,
+                        i + 1
+                    )));
+                }
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| {
+                invalid(format!("nuclei: expected `key = value` at line {}", i + 1))
+            })?;
+            let (key, value) = (key.trim(), value.trim());
+            let key = KEYS.iter().find(|k| **k == key).ok_or_else(|| {
+                invalid(format!("nuclei: unknown key `{}` at line {}", key, i + 1))
+            })?;
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+
+            settings.push(Setting {
+                key,
+                value: value.to_owned(),
+                origin: format!("`{}` at line {}", key, i + 1),
+            });
+        }
+
+        build(settings)
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+/// Applies the preset and then the rest of the settings in order.
+fn build(settings: Vec<Setting>) -> io::Result<NucleiConfig> {
+    let last = |key| settings.iter().rev().find(|s| s.key == key);
+
+    let mut config = NucleiConfig::default();
+    if let Some(preset) = last("preset") {
+        let queue_len = match last("queue_len") {
+            Some(s) => s.u32()?,
+            None => config.iouring.queue_len,
+        };
+        config.iouring = match preset.value.as_str() {
+            "interrupt_driven" => IoUringConfiguration::interrupt_driven(queue_len),
+            "low_latency_driven" => IoUringConfiguration::low_latency_driven(queue_len),
+            "kernel_poll_only" => IoUringConfiguration::kernel_poll_only(queue_len),
+            "io_poll" => IoUringConfiguration::io_poll(queue_len),
+            _ => {
+                return Err(preset.invalid(
+                    "`interrupt_driven`, `low_latency_driven`, `kernel_poll_only` or `io_poll`",
+                ))
+            }
+        };
+    }
+
+    for s in settings.iter() {
+        let iouring = &mut config.iouring;
+        match s.key {
+            "preset" => {}
+            "backend" => {
+                config.backend = match s.value.to_ascii_lowercase().as_str() {
+                    "iouring" | "io_uring" => Some(IoBackend::IoUring),
+                    "epoll" => Some(IoBackend::Epoll),
+                    "kqueue" => Some(IoBackend::Kqueue),
+                    "auto" => None,
+                    _ => return Err(s.invalid("`iouring`, `epoll`, `kqueue` or `auto`")),
+                }
+            }
+            "queue_len" => iouring.queue_len = s.u32()?,
+            "sqpoll_ms" => iouring.sqpoll_wake_interval = s.optional_u32()?,
+            "sqpoll_cpu" => iouring.sqpoll_cpu = s.optional_u32()?,
+            "bounded_workers" => iouring.per_numa_bounded_worker_count = s.optional_u32()?,
+            "unbounded_workers" => iouring.per_numa_unbounded_worker_count = s.optional_u32()?,
+            "aggressive_poll" => iouring.aggressive_poll = s.bool()?,
+            "iopoll" => iouring.iopoll_enabled = s.bool()?,
+            "backpressure" => {
+                iouring.backpressure = match s.value.to_ascii_lowercase().as_str() {
+                    "wait" => SubmissionBackpressure::Wait,
+                    "would_block" => SubmissionBackpressure::WouldBlock,
+                    "overflow" => SubmissionBackpressure::Overflow,
+                    _ => return Err(s.invalid("`wait`, `would_block` or `overflow`")),
+                }
+            }
+            "batching" => {
+                iouring.batching = match s.value.to_ascii_lowercase().as_str() {
+                    "immediate" => SubmissionBatching::Immediate,
+                    _ => SubmissionBatching::Threshold(
+                        s.value
+                            .parse()
+                            .map_err(|_| s.invalid("`immediate` or an unsigned integer"))?,
+                    ),
+                }
+            }
+            "cq_len" => iouring.cq_len = s.optional_u32()?,
+            "clamp" => iouring.clamp = s.bool()?,
+            "single_issuer" => iouring.single_issuer = s.bool()?,
+            "coop_taskrun" => iouring.coop_taskrun = s.bool()?,
+            "defer_taskrun" => iouring.defer_taskrun = s.bool()?,
+            "submit_all" => iouring.submit_all = s.bool()?,
+            "recv_buf_ring_len" => iouring.recv_buf_ring_len = s.u32()?,
+            "recv_buf_size" => iouring.recv_buf_size = s.u32()?,
+            "file_table_len" => iouring.file_table_len = s.u32()?,
+            "epoll_max_events" => config.epoll.max_events = s.optional_u32()?.map(|n| n as usize),
+            "epoll_trigger" => {
+                config.epoll.trigger = match s.value.to_ascii_lowercase().as_str() {
+                    "edge" => EpollTrigger::Edge,
+                    "level" => EpollTrigger::Level,
+                    _ => return Err(s.invalid("`edge` or `level`")),
+                }
+            }
+            "epoll_exclusive" => config.epoll.exclusive = s.bool()?,
+            "epoll_busy_poll_usecs" | "epoll_busy_poll_budget" | "epoll_prefer_busy_poll" => {}
+            _ => unreachable!("nuclei: configuration key without a setter"),
+        }
+    }
+
+    // Busy polling is enabled by its duration, the rest tunes it.
+    let budget = last("epoll_busy_poll_budget");
+    let prefer = last("epoll_prefer_busy_poll");
+    match last("epoll_busy_poll_usecs") {
+        Some(usecs) => {
+            config.epoll.busy_poll = usecs
+                .optional_u32()?
+                .map(|usecs| -> io::Result<EpollBusyPoll> {
+                    Ok(EpollBusyPoll {
+                        usecs,
+                        budget: match budget {
+                            Some(s) => s
+                                .value
+                                .parse()
+                                .map_err(|_| s.invalid("an unsigned 16-bit integer"))?,
+                            None => BUSY_POLL_BUDGET,
+                        },
+                        prefer: prefer.map(Setting::bool).transpose()?.unwrap_or(false),
+                    })
+                })
+                .transpose()?
+        }
+        None => {
+            if let Some(s) = budget.or(prefer) {
+                return Err(invalid(format!(
+                    "nuclei: {} requires `epoll_busy_poll_usecs`",
+                    s.origin
+                )));
+            }
+        }
+    }
+
+    Ok(config)
+}
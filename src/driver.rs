@@ -0,0 +1,101 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, Thread};
+
+use lever::sync::prelude::*;
+
+use crate::Proactor;
+
+/// Events dispatched per wait of the thread that drives the proactor.
+const MAX_EVENTS: usize = 64;
+
+///
+/// Driver slot of a proactor, only one of the threads that block on it waits on it at a time.
+///
+/// The rest of the threads park, and they are unparked to take the slot over when it is released.
+pub(crate) struct Driver {
+    busy: AtomicBool,
+    parked: TTas<Vec<Thread>>,
+}
+
+impl Driver {
+    pub(crate) fn new() -> Driver {
+        Driver {
+            busy: AtomicBool::new(false),
+            parked: TTas::new(Vec::new()),
+        }
+    }
+
+    fn acquire(&self) -> bool {
+        !self.busy.swap(true, Ordering::AcqRel)
+    }
+
+    fn release(&self) {
+        self.busy.store(false, Ordering::Release);
+        for thread in self.parked.lock().drain(..) {
+            thread.unpark();
+        }
+    }
+
+    /// Parks the current thread until the slot is released, or the thread is unparked otherwise.
+    fn park(&self) {
+        self.parked.lock().push(thread::current());
+        if self.busy.load(Ordering::Acquire) {
+            thread::park();
+        }
+    }
+}
+
+///
+/// Parks the thread that blocks on a future until its waker fires.
+pub(crate) struct Parker {
+    proactor: Proactor,
+    thread: Thread,
+    notified: AtomicBool,
+    /// Whether the thread waits on the proactor, instead of being parked.
+    driving: AtomicBool,
+}
+
+impl Parker {
+    pub(crate) fn new(proactor: Proactor) -> Parker {
+        Parker {
+            proactor,
+            thread: thread::current(),
+            notified: AtomicBool::new(false),
+            driving: AtomicBool::new(false),
+        }
+    }
+
+    /// Wakes the thread, whether it is parked or waiting on the proactor.
+    pub(crate) fn unpark(&self) {
+        // Thread is already on its way out of the wait when the waker fired before.
+        if self.notified.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        // Waker of a future fires on its own thread while completions are dispatched,
+        // the thread returns from the wait right after anyway.
+        if self.driving.load(Ordering::SeqCst) && thread::current().id() != self.thread.id() {
+            let _ = self.proactor.0.wake();
+        }
+        self.thread.unpark();
+    }
+
+    /// Blocks until the waker fires, driving the proactor meanwhile if no other thread does.
+    pub(crate) fn park(&self) {
+        while !self.notified.swap(false, Ordering::SeqCst) {
+            let driver = self.proactor.0.driver();
+            if self.proactor.is_shutdown() {
+                thread::park();
+            } else if driver.acquire() {
+                self.driving.store(true, Ordering::SeqCst);
+                // Waker might have fired before it could see that the thread is driving.
+                if !self.notified.load(Ordering::SeqCst) {
+                    let _ = self.proactor.wait_parked(MAX_EVENTS);
+                }
+                self.driving.store(false, Ordering::SeqCst);
+                driver.release();
+            } else {
+                driver.park();
+            }
+        }
+    }
+}
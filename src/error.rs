@@ -0,0 +1,87 @@
+use std::{error, fmt, io};
+
+///
+/// Reason that the proactor can't be built, see [Proactor::try_with_config](crate::Proactor::try_with_config).
+#[derive(Debug)]
+pub enum NucleiError {
+    /// Process-wide proactor is already initialized.
+    AlreadyInitialized,
+    /// Configuration is rejected before reaching the kernel, like conflicting io_uring setup flags.
+    InvalidConfig(io::Error),
+    /// Kernel doesn't support the backend or a configured feature of it.
+    UnsupportedKernel(io::Error),
+    /// Kernel denied the setup, like SQPOLL without privileges, seccomp or `io_uring_disabled` sysctl.
+    PermissionDenied(io::Error),
+    /// Resource limits are hit, like `RLIMIT_MEMLOCK` for the io_uring rings or the open files limit.
+    ResourceLimit(io::Error),
+    /// Any other failure of the backend setup.
+    Io(io::Error),
+}
+
+impl From<io::Error> for NucleiError {
+    fn from(e: io::Error) -> NucleiError {
+        #[cfg(unix)]
+        match e.raw_os_error() {
+            // Kernel rejects the setup flags that it doesn't know.
+            Some(libc::EINVAL) | Some(libc::ENOSYS) | Some(libc::EOPNOTSUPP) => {
+                return NucleiError::UnsupportedKernel(e)
+            }
+            Some(libc::ENOMEM) | Some(libc::EMFILE) | Some(libc::ENFILE) | Some(libc::EAGAIN) => {
+                return NucleiError::ResourceLimit(e)
+            }
+            _ => {}
+        }
+
+        match e.kind() {
+            io::ErrorKind::PermissionDenied => NucleiError::PermissionDenied(e),
+            io::ErrorKind::Unsupported => NucleiError::UnsupportedKernel(e),
+            io::ErrorKind::OutOfMemory => NucleiError::ResourceLimit(e),
+            io::ErrorKind::InvalidInput => NucleiError::InvalidConfig(e),
+            _ => NucleiError::Io(e),
+        }
+    }
+}
+
+impl From<NucleiError> for io::Error {
+    fn from(e: NucleiError) -> io::Error {
+        match e {
+            NucleiError::AlreadyInitialized => io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                NucleiError::AlreadyInitialized,
+            ),
+            NucleiError::InvalidConfig(e)
+            | NucleiError::UnsupportedKernel(e)
+            | NucleiError::PermissionDenied(e)
+            | NucleiError::ResourceLimit(e)
+            | NucleiError::Io(e) => e,
+        }
+    }
+}
+
+impl fmt::Display for NucleiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NucleiError::AlreadyInitialized => {
+                write!(f, "nuclei: proactor instance is already initialized")
+            }
+            NucleiError::InvalidConfig(e) => write!(f, "nuclei: invalid configuration: {}", e),
+            NucleiError::UnsupportedKernel(e) => write!(f, "nuclei: unsupported by kernel: {}", e),
+            NucleiError::PermissionDenied(e) => write!(f, "nuclei: permission denied: {}", e),
+            NucleiError::ResourceLimit(e) => write!(f, "nuclei: resource limit is hit: {}", e),
+            NucleiError::Io(e) => write!(f, "nuclei: cannot initialize IO backend: {}", e),
+        }
+    }
+}
+
+impl error::Error for NucleiError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            NucleiError::AlreadyInitialized => None,
+            NucleiError::InvalidConfig(e)
+            | NucleiError::UnsupportedKernel(e)
+            | NucleiError::PermissionDenied(e)
+            | NucleiError::ResourceLimit(e)
+            | NucleiError::Io(e) => Some(e),
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use std::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
+use std::fs::File;
+use std::future::Future;
+use std::io;
+use std::net::TcpStream;
+use std::ops::{Deref, DerefMut};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::pin::Pin;
+use std::ptr::NonNull;
+use std::slice;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+use lever::sync::prelude::*;
+
+use crate::syscore::Processor;
+use crate::{Handle, Proactor};
+
+/// Most buffers that io_uring accepts in a registration.
+const MAX_BUFFERS: usize = 1 << 14;
+
+/// Buffers are page aligned, so they don't share pages that the kernel pins.
+const PAGE_SIZE: usize = 4096;
+
+///
+/// Pool of buffers that are registered to the proactor as io_uring fixed buffers.
+///
+/// The kernel pins the pages of the buffers once, when the pool is built, instead of on every
+/// operation. Buffers are leased as [FixedBuf]s and go back to the pool when the lease is dropped.
+///
+/// io_uring allows a single registration per ring, so building a second pool on the same proactor
+/// fails with [io::ErrorKind::AlreadyExists] while the first one is alive. On epoll, buffers
+/// aren't registered and operations on them are the plain ones.
+///
+/// Cloning the pool gives another reference to the same buffers.
+#[derive(Clone)]
+pub struct FixedBufferPool {
+    slots: Arc<Slots>,
+}
+
+impl FixedBufferPool {
+    ///
+    /// Allocates `count` buffers of `size` bytes and registers them to the current proactor.
+    ///
+    /// On kernels before 5.12, registered buffers are accounted to `RLIMIT_MEMLOCK`.
+    pub fn new(count: usize, size: usize) -> io::Result<FixedBufferPool> {
+        if count == 0 || count > MAX_BUFFERS || size == 0 || size > u32::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "nuclei: fixed buffer pool needs 1 to 16384 buffers of up to 4 GiB",
+            ));
+        }
+
+        let stride = size.next_multiple_of(PAGE_SIZE);
+        let layout = stride
+            .checked_mul(count)
+            .and_then(|len| Layout::from_size_align(len, PAGE_SIZE).ok())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "nuclei: fixed buffer pool is too large",
+                )
+            })?;
+        let data = NonNull::new(unsafe { alloc_zeroed(layout) })
+            .unwrap_or_else(|| handle_alloc_error(layout));
+
+        let iovecs: Vec<libc::iovec> = (0..count)
+            .map(|i| libc::iovec {
+                iov_base: unsafe { data.as_ptr().add(i * stride) } as *mut _,
+                iov_len: size,
+            })
+            .collect();
+
+        let proactor = Proactor::current();
+        if let Err(e) = proactor.inner().register_buffers(&iovecs) {
+            unsafe { dealloc(data.as_ptr(), layout) };
+            return Err(match e.raw_os_error() {
+                Some(libc::EBUSY) => io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "nuclei: fixed buffers are already registered to the proactor",
+                ),
+                _ => e,
+            });
+        }
+
+        Ok(FixedBufferPool {
+            slots: Arc::new(Slots {
+                proactor,
+                data,
+                layout,
+                stride,
+                size,
+                free: TTas::new((0..count as u16).rev().collect()),
+                waiters: TTas::new(Vec::new()),
+            }),
+        })
+    }
+
+    ///
+    /// Leases a buffer, [None] if all of them are leased.
+    pub fn try_acquire(&self) -> Option<FixedBuf> {
+        let index = self.slots.free.lock().pop()?;
+
+        Some(FixedBuf {
+            slots: self.slots.clone(),
+            index,
+            len: 0,
+        })
+    }
+
+    ///
+    /// Leases a buffer, waiting for one to be returned if all of them are leased.
+    pub fn acquire(&self) -> Acquire<'_> {
+        Acquire { pool: self }
+    }
+
+    ///
+    /// Number of buffers in the pool.
+    pub fn count(&self) -> usize {
+        self.slots.layout.size() / self.slots.stride
+    }
+
+    ///
+    /// Size of each buffer in bytes.
+    pub fn buffer_size(&self) -> usize {
+        self.slots.size
+    }
+
+    ///
+    /// Number of buffers that aren't leased.
+    pub fn available(&self) -> usize {
+        self.slots.free.lock().len()
+    }
+
+    ///
+    /// Proactor that the buffers are registered to.
+    pub fn proactor(&self) -> &Proactor {
+        &self.slots.proactor
+    }
+}
+
+///
+/// Future that leases a buffer from the pool, returned by [FixedBufferPool::acquire].
+pub struct Acquire<'a> {
+    pool: &'a FixedBufferPool,
+}
+
+impl Future for Acquire<'_> {
+    type Output = FixedBuf;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(buf) = self.pool.try_acquire() {
+            return Poll::Ready(buf);
+        }
+
+        // Register before checking again, a buffer might have been returned in between.
+        self.pool.slots.waiters.lock().push(cx.waker().clone());
+        match self.pool.try_acquire() {
+            Some(buf) => Poll::Ready(buf),
+            None => Poll::Pending,
+        }
+    }
+}
+
+///
+/// Memory of the pool, which stays registered until the pool and all its leases are dropped.
+struct Slots {
+    proactor: Proactor,
+    data: NonNull<u8>,
+    layout: Layout,
+    /// Distance between the buffers, their size rounded up to the page size.
+    stride: usize,
+    size: usize,
+    /// Indices of the buffers that aren't leased.
+    free: TTas<Vec<u16>>,
+    /// Tasks waiting for a buffer to be returned.
+    waiters: TTas<Vec<Waker>>,
+}
+
+// Every buffer is accessed only through the single lease of it.
+unsafe impl Send for Slots {}
+unsafe impl Sync for Slots {}
+
+impl Slots {
+    fn release(&self, index: u16) {
+        self.free.lock().push(index);
+        // Waiters race for the buffer, the ones that lose register again.
+        let waiters = std::mem::take(&mut *self.waiters.lock());
+        waiters.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl Drop for Slots {
+    fn drop(&mut self) {
+        // Leases of in-flight operations are held until the kernel is done with them,
+        // so nothing refers to the buffers anymore.
+        let _ = self.proactor.inner().unregister_buffers();
+        unsafe { dealloc(self.data.as_ptr(), self.layout) };
+    }
+}
+
+///
+/// Lease of a buffer of a [FixedBufferPool].
+///
+/// It derefs to the initialized part of the buffer, the length is set by reads and by
+/// [FixedBuf::put_slice]. The buffer goes back to the pool when the lease is dropped.
+pub struct FixedBuf {
+    slots: Arc<Slots>,
+    index: u16,
+    len: usize,
+}
+
+impl FixedBuf {
+    ///
+    /// Index of the buffer in the registration.
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    ///
+    /// Size of the buffer, reads fill up to it.
+    pub fn capacity(&self) -> usize {
+        self.slots.size
+    }
+
+    ///
+    /// Length of the initialized part of the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    ///
+    /// Whether nothing is in the buffer.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    ///
+    /// Empties the buffer, the capacity is kept.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    ///
+    /// Appends as much of the data as fits, returns the number of bytes appended.
+    pub fn put_slice(&mut self, data: &[u8]) -> usize {
+        let n = data.len().min(self.capacity() - self.len);
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr(), self.as_mut_ptr().add(self.len), n);
+        }
+        self.len += n;
+        n
+    }
+
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut u8 {
+        unsafe {
+            self.slots
+                .data
+                .as_ptr()
+                .add(self.index as usize * self.slots.stride)
+        }
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const u8 {
+        unsafe {
+            self.slots
+                .data
+                .as_ptr()
+                .add(self.index as usize * self.slots.stride)
+        }
+    }
+
+    /// Sets the length after the buffer is filled up to it.
+    pub(crate) fn set_len(&mut self, len: usize) {
+        self.len = len.min(self.capacity());
+    }
+
+    /// Fails if the buffer is registered to a proactor other than the given one.
+    fn check_proactor(&self, proactor: &Proactor) -> io::Result<()> {
+        if self.slots.proactor != *proactor {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "nuclei: fixed buffer is registered to another proactor",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Deref for FixedBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for FixedBuf {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        let len = self.len;
+        unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), len) }
+    }
+}
+
+impl Drop for FixedBuf {
+    fn drop(&mut self) {
+        self.slots.release(self.index);
+    }
+}
+
+impl std::fmt::Debug for FixedBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FixedBuf")
+            .field("index", &self.index)
+            .field("len", &self.len)
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl Handle<File> {
+    ///
+    /// Reads into the fixed buffer from the offset of the file, up to the capacity of the buffer.
+    ///
+    /// Returns the number of bytes read along with the buffer, whose length is set to it.
+    /// The lease goes back to the pool if the read fails.
+    pub async fn read_fixed_at(&self, buf: FixedBuf, offset: u64) -> io::Result<(usize, FixedBuf)> {
+        buf.check_proactor(&self.proactor)?;
+        let fd: RawFd = self.as_raw_fd();
+
+        self.proactor
+            .route(Processor::processor_read_fixed(
+                &fd,
+                buf,
+                offset as _,
+                self.read_timeout,
+            ))
+            .await
+    }
+
+    ///
+    /// Writes the contents of the fixed buffer to the offset of the file.
+    ///
+    /// Returns the number of bytes written along with the buffer.
+    /// The lease goes back to the pool if the write fails.
+    pub async fn write_fixed_at(
+        &self,
+        buf: FixedBuf,
+        offset: u64,
+    ) -> io::Result<(usize, FixedBuf)> {
+        buf.check_proactor(&self.proactor)?;
+        let fd: RawFd = self.as_raw_fd();
+
+        self.proactor
+            .route(Processor::processor_write_fixed(&fd, buf, offset as _))
+            .await
+    }
+}
+
+macro_rules! impl_fixed_socket {
+    ($name:ident) => {
+        impl Handle<$name> {
+            ///
+            /// Receives into the fixed buffer, up to the capacity of the buffer.
+            ///
+            /// Returns the number of bytes received along with the buffer, whose length is set
+            /// to it. The lease goes back to the pool if the receive fails.
+            pub async fn recv_fixed(&self, buf: FixedBuf) -> io::Result<(usize, FixedBuf)> {
+                buf.check_proactor(&self.proactor)?;
+
+                self.proactor
+                    .route(Processor::processor_recv_fixed(
+                        self.get_ref(),
+                        buf,
+                        self.read_timeout,
+                    ))
+                    .await
+            }
+
+            ///
+            /// Sends the contents of the fixed buffer.
+            ///
+            /// Returns the number of bytes sent along with the buffer.
+            /// The lease goes back to the pool if the send fails.
+            pub async fn send_fixed(&self, buf: FixedBuf) -> io::Result<(usize, FixedBuf)> {
+                buf.check_proactor(&self.proactor)?;
+
+                self.proactor
+                    .route(Processor::processor_send_fixed(
+                        self.get_ref(),
+                        buf,
+                        self.write_timeout,
+                    ))
+                    .await
+            }
+        }
+    };
+}
+
+impl_fixed_socket!(TcpStream);
+impl_fixed_socket!(UnixStream);
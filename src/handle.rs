@@ -1,13 +1,17 @@
 use lever::prelude::*;
 use std::fmt;
+#[cfg(all(feature = "iouring", target_os = "linux"))]
+use std::os::unix::io::RawFd;
 use std::{
     future::Future,
     io,
     ops::{Deref, DerefMut},
     pin::Pin,
     sync::Arc,
+    time::Duration,
 };
 
+use crate::proactor::Proactor;
 use crate::syscore::{CompletionChan, StoreFile};
 
 ///
@@ -19,6 +23,7 @@ pub type AsyncOp<T> = Pin<Box<dyn Future<Output = io::Result<T>>>>;
 pub trait HandleOpRegisterer {
     fn read_registerer(&self) -> Arc<TTas<Option<AsyncOp<usize>>>>;
     fn write_registerer(&self) -> Arc<TTas<Option<AsyncOp<usize>>>>;
+    fn proactor(&self) -> &Proactor;
 }
 
 ///
@@ -27,6 +32,15 @@ pub trait HandleOpRegisterer {
 /// This handle wraps io element, its' completion channel, if file, file buffers and input output operations
 /// It is single handedly responsible for dispatching correct operations to IO driver.
 /// Speed of IO bound to speed of executor, handle neither interferes with executor nor tightly coupled.
+///
+/// # Dropping operations
+///
+/// On io_uring, the kernel accesses the buffer of an operation until the operation completes.
+/// Dropping the future of an in-flight operation on a borrowed buffer, like `recv(&mut buf)` or
+/// `send(&buf)`, cancels the operation and blocks the thread until the kernel acknowledges the
+/// cancellation. That takes a trip to the kernel, or up to a driver tick per round while another
+/// thread drives the ring. Operations on owned buffers, like `send_zc` and the fixed buffer ones,
+/// don't block when they are dropped, the buffer is released along with the cancellation instead.
 pub struct Handle<T> {
     /// IO task element
     pub(crate) io_task: Option<T>,
@@ -38,6 +52,51 @@ pub struct Handle<T> {
     pub(crate) read: Arc<TTas<Option<AsyncOp<usize>>>>,
     /// Completion callback for write
     pub(crate) write: Arc<TTas<Option<AsyncOp<usize>>>>,
+    /// Timeout applied to the read operations
+    pub(crate) read_timeout: Option<Duration>,
+    /// Timeout applied to the write operations
+    pub(crate) write_timeout: Option<Duration>,
+    /// Proactor that the operations are submitted to
+    pub(crate) proactor: Proactor,
+    /// Descriptor that is registered to the file table of the proactor, see `register_direct`
+    #[cfg(all(feature = "iouring", target_os = "linux"))]
+    pub(crate) direct: Option<RawFd>,
+}
+
+///
+/// Descriptor that is installed straight into the file table of the io_uring proactor, it never
+/// takes a slot in the descriptor table of the process.
+///
+/// Direct descriptors are accepted with [Handle::accept_direct] and
+/// [Handle::accept_multi_direct], or opened with [Handle::open_direct]. Operations refer to it by
+/// its slot, and the slot is closed when it is dropped.
+#[cfg(all(feature = "iouring", target_os = "linux"))]
+pub struct Direct {
+    pub(crate) slot: u32,
+    pub(crate) proactor: Proactor,
+}
+
+#[cfg(all(feature = "iouring", target_os = "linux"))]
+impl Direct {
+    ///
+    /// Slot of the descriptor in the file table of the ring.
+    pub fn slot(&self) -> u32 {
+        self.slot
+    }
+}
+
+#[cfg(all(feature = "iouring", target_os = "linux"))]
+impl Drop for Direct {
+    fn drop(&mut self) {
+        self.proactor.inner().close_slot(self.slot);
+    }
+}
+
+#[cfg(all(feature = "iouring", target_os = "linux"))]
+impl fmt::Debug for Direct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Direct").field("slot", &self.slot).finish()
+    }
 }
 
 unsafe impl<T> Send for Handle<T> {}
@@ -56,6 +115,43 @@ impl<T> Handle<T> {
         self.io_task.take().unwrap()
     }
 
+    ///
+    /// Proactor that owns this handle, which is the current one when the handle is created.
+    pub fn proactor(&self) -> &Proactor {
+        &self.proactor
+    }
+
+    ///
+    /// Sets the timeout of the read operations, [None] waits indefinitely.
+    ///
+    /// An operation that doesn't complete in time is cancelled and fails with
+    /// [io::ErrorKind::TimedOut]. Regular files are read without a timeout on the epoll backend,
+    /// since they are always readable.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    ///
+    /// Timeout of the read operations.
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+
+    ///
+    /// Sets the timeout of the write operations, [None] waits indefinitely.
+    ///
+    /// An operation that doesn't complete in time is cancelled and fails with
+    /// [io::ErrorKind::TimedOut].
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.write_timeout = timeout;
+    }
+
+    ///
+    /// Timeout of the write operations.
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.write_timeout
+    }
+
     // #[cfg(all(feature = "iouring", target_os = "linux"))]
     // unsafe_unpinned!(store_file: Option<StoreFile>);
     //
@@ -72,6 +168,10 @@ impl<T> HandleOpRegisterer for Handle<T> {
     fn write_registerer(&self) -> Arc<TTas<Option<AsyncOp<usize>>>> {
         self.write.clone()
     }
+
+    fn proactor(&self) -> &Proactor {
+        &self.proactor
+    }
 }
 
 impl<T> HandleOpRegisterer for &Handle<T> {
@@ -82,6 +182,25 @@ impl<T> HandleOpRegisterer for &Handle<T> {
     fn write_registerer(&self) -> Arc<TTas<Option<AsyncOp<usize>>>> {
         self.write.clone()
     }
+
+    fn proactor(&self) -> &Proactor {
+        &self.proactor
+    }
+}
+
+impl<T> Drop for Handle<T> {
+    fn drop(&mut self) {
+        // Drop in-flight operations first, so they are cancelled while the IO element
+        // and its file buffers are still around.
+        self.read.lock().take();
+        self.write.lock().take();
+
+        // Slot is closed before the descriptor, which is closed with the IO element.
+        #[cfg(all(feature = "iouring", target_os = "linux"))]
+        if let Some(fd) = self.direct.take() {
+            self.proactor.inner().close_direct(fd);
+        }
+    }
 }
 
 impl<T> Deref for Handle<T> {
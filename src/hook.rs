@@ -0,0 +1,95 @@
+use crate::latency::OpClass;
+
+///
+/// Lifecycle point of an operation that an [OpEvent] is emitted for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpEventKind {
+    /// Operation is handed to the proactor.
+    Submit,
+    /// Operation completed successfully.
+    Complete,
+    /// Cancellation of the operation is requested, it still completes afterwards.
+    Cancel,
+    /// Operation completed with an error.
+    Error,
+}
+
+///
+/// Event of an operation that is passed to the [EventHook].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpEvent {
+    /// Lifecycle point of the operation.
+    pub kind: OpEventKind,
+    /// io_uring opcode of the operation, [None] on epoll.
+    pub opcode: Option<u8>,
+    /// Class of the operation, [None] for operations that aren't classified, like timers.
+    pub class: Option<OpClass>,
+    /// File descriptor the operation is on, `-1` if it isn't on one.
+    pub fd: i32,
+    /// Id of the operation, the same for all events of it. This is the `user_data` on io_uring.
+    pub user_data: u64,
+    /// Result of the completion, negated errno for errors.
+    /// On epoll, readiness events are the result of a completion. Zero for the other kinds.
+    pub result: i32,
+    /// Bytes transferred by reads, writes, sends and receives when they complete, zero otherwise.
+    /// Always zero on epoll, operations complete on readiness there, before the bytes are
+    /// transferred.
+    pub bytes: usize,
+}
+
+///
+/// Hook that is called on the submission, completion, cancellation and failure of operations,
+/// see [NucleiConfig::event_hook](crate::config::NucleiConfig::event_hook).
+///
+/// It is called on the IO path, so it should be cheap and it must not block. It is never called
+/// with the proactor locks held, completions are dispatched first and their events are emitted
+/// afterwards. So the proactor can be queried from within it.
+///
+/// Closures that take an [OpEvent] are hooks as well.
+pub trait EventHook: Send + Sync {
+    /// Called for every event of the operations.
+    fn on_event(&self, event: &OpEvent);
+}
+
+impl<F> EventHook for F
+where
+    F: Fn(&OpEvent) + Send + Sync,
+{
+    fn on_event(&self, event: &OpEvent) {
+        self(event)
+    }
+}
+
+impl OpEvent {
+    /// Completion event of io_uring, split into success and error by the sign of the result.
+    pub(crate) fn completion(
+        opcode: u8,
+        class: Option<OpClass>,
+        fd: i32,
+        user_data: u64,
+        result: i32,
+    ) -> OpEvent {
+        let transfers = matches!(
+            class,
+            Some(OpClass::Read | OpClass::Write | OpClass::Recv | OpClass::Send)
+        );
+
+        OpEvent {
+            kind: if result < 0 {
+                OpEventKind::Error
+            } else {
+                OpEventKind::Complete
+            },
+            opcode: Some(opcode),
+            class,
+            fd,
+            user_data,
+            result,
+            bytes: if transfers && result > 0 {
+                result as usize
+            } else {
+                0
+            },
+        }
+    }
+}
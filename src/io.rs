@@ -0,0 +1,188 @@
+use std::fs::File;
+use std::io;
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use crate::syscore::Processor;
+use crate::{Handle, Proactor};
+
+/// Bytes that a transfer moves through the internal pipe at once, the default capacity of a pipe.
+const PIPE_SIZE: usize = 1 << 16;
+
+///
+/// Moves up to `len` bytes from one handle to the other, without copying them through userspace.
+///
+/// Bytes go through an internal pipe, so neither of the handles has to be a pipe. Input is read
+/// from its current position, for files see [sendfile]. Returns the number of bytes moved, which
+/// is `0` at the end of the input.
+pub async fn splice<A: AsRawFd, B: AsRawFd>(
+    from: &Handle<A>,
+    to: &Handle<B>,
+    len: usize,
+) -> io::Result<usize> {
+    let pipe = Pipe::new(&from.proactor)?;
+
+    from.proactor
+        .route(pipe.transfer(from.as_raw_fd(), None, to.as_raw_fd(), len))
+        .await
+}
+
+///
+/// Sends `len` bytes of the file, starting at `offset`, to the handle without copying them
+/// through userspace.
+///
+/// Position of the file handle isn't used nor advanced. Returns the number of bytes sent, which is
+/// less than `len` only if the file ends before.
+pub async fn sendfile<B: AsRawFd>(
+    file: &Handle<File>,
+    to: &Handle<B>,
+    offset: u64,
+    len: usize,
+) -> io::Result<usize> {
+    let pipe = Pipe::new(&file.proactor)?;
+
+    file.proactor
+        .route(async {
+            let mut sent = 0;
+            while sent < len {
+                let off_in = Some(offset + sent as u64);
+                match pipe
+                    .transfer(file.as_raw_fd(), off_in, to.as_raw_fd(), len - sent)
+                    .await?
+                {
+                    0 => break,
+                    n => sent += n,
+                }
+            }
+            Ok(sent)
+        })
+        .await
+}
+
+///
+/// Duplicates up to `len` bytes from one pipe to the other, without consuming them from the input.
+///
+/// Both handles have to refer to pipes. Returns the number of bytes duplicated, which is `0` when
+/// the input pipe is empty and all of its writers are closed.
+pub async fn tee<A: AsRawFd, B: AsRawFd>(
+    from: &Handle<A>,
+    to: &Handle<B>,
+    len: usize,
+) -> io::Result<usize> {
+    from.proactor
+        .route(Processor::processor_tee(
+            &from.as_raw_fd(),
+            &to.as_raw_fd(),
+            len,
+        ))
+        .await
+}
+
+///
+/// Copies bytes in both directions between the handles until both of them reach the end of their
+/// input, without copying them through userspace.
+///
+/// Writing side of a socket is shut down once its peer reaches the end of its input, so the end
+/// of the stream is passed on. Returns the number of bytes copied from `a` to `b`, and from `b`
+/// to `a`.
+pub async fn copy_bidirectional<A: AsRawFd, B: AsRawFd>(
+    a: &Handle<A>,
+    b: &Handle<B>,
+) -> io::Result<(u64, u64)> {
+    let (a_to_b, b_to_a) = (Pipe::new(&a.proactor)?, Pipe::new(&a.proactor)?);
+    let (a, b) = (a.as_raw_fd(), b.as_raw_fd());
+
+    a_to_b
+        .proactor
+        .route(futures::future::try_join(
+            a_to_b.copy(a, b),
+            b_to_a.copy(b, a),
+        ))
+        .await
+}
+
+/// Pipe pair that the transfers between handles go through.
+struct Pipe {
+    proactor: Proactor,
+    read: OwnedFd,
+    write: OwnedFd,
+}
+
+impl Pipe {
+    fn new(proactor: &Proactor) -> io::Result<Pipe> {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Pipe {
+            proactor: proactor.clone(),
+            read: unsafe { OwnedFd::from_raw_fd(fds[0]) },
+            write: unsafe { OwnedFd::from_raw_fd(fds[1]) },
+        })
+    }
+
+    /// Moves up to `len` bytes from the input to the output, returns `0` at the end of the input.
+    async fn transfer(
+        &self,
+        fd_in: RawFd,
+        off_in: Option<u64>,
+        fd_out: RawFd,
+        len: usize,
+    ) -> io::Result<usize> {
+        let filled = Processor::processor_splice(
+            &fd_in,
+            off_in,
+            &self.write.as_raw_fd(),
+            len.min(PIPE_SIZE),
+        )
+        .await?;
+
+        // Pipe is drained before returning, so no bytes are left behind for the next transfer.
+        let mut drained = 0;
+        while drained < filled {
+            match Processor::processor_splice(
+                &self.read.as_raw_fd(),
+                None,
+                &fd_out,
+                filled - drained,
+            )
+            .await?
+            {
+                0 => return Err(io::ErrorKind::WriteZero.into()),
+                n => drained += n,
+            }
+        }
+
+        Ok(filled)
+    }
+
+    /// Moves bytes from the input to the output until the end of the input.
+    async fn copy(&self, fd_in: RawFd, fd_out: RawFd) -> io::Result<u64> {
+        let mut copied = 0;
+        loop {
+            match self.transfer(fd_in, None, fd_out, PIPE_SIZE).await? {
+                0 => break,
+                n => copied += n as u64,
+            }
+        }
+
+        if unsafe { libc::shutdown(fd_out, libc::SHUT_WR) } == -1 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::ENOTSOCK) {
+                return Err(err);
+            }
+        }
+
+        Ok(copied)
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        // Interests of a cancelled transfer would outlive the pipe otherwise.
+        let inner = self.proactor.inner();
+        inner.deregister_io(self.read.as_raw_fd());
+        inner.deregister_io(self.write.as_raw_fd());
+    }
+}
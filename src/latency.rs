@@ -0,0 +1,137 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Bucket `i` counts the latencies within `[2^i, 2^(i+1))` nanoseconds, zero is counted in the first.
+const BUCKETS: usize = 64;
+
+///
+/// Class of the operations whose submit-to-completion latency is recorded,
+/// see [Proactor::latency](crate::Proactor::latency).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OpClass {
+    /// File reads, vectored or not.
+    Read,
+    /// File writes, vectored or not.
+    Write,
+    /// Socket receives, including `recvmsg`.
+    Recv,
+    /// Socket sends, including `sendmsg`.
+    Send,
+    /// Accepting connections.
+    Accept,
+    /// Connecting sockets.
+    Connect,
+    /// File metadata queries.
+    Statx,
+}
+
+impl OpClass {
+    /// All operation classes.
+    pub const ALL: [OpClass; 7] = [
+        OpClass::Read,
+        OpClass::Write,
+        OpClass::Recv,
+        OpClass::Send,
+        OpClass::Accept,
+        OpClass::Connect,
+        OpClass::Statx,
+    ];
+}
+
+///
+/// Snapshot of the latency distribution of an operation class.
+///
+/// Buckets are powers of two of nanoseconds, so quantiles are accurate to a factor of two.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    buckets: [u64; BUCKETS],
+    sum_ns: u64,
+}
+
+impl LatencyHistogram {
+    /// Number of recorded completions.
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Mean latency, [None] if nothing is recorded.
+    pub fn mean(&self) -> Option<Duration> {
+        let count = self.count();
+        (count > 0).then(|| Duration::from_nanos(self.sum_ns / count))
+    }
+
+    /// Upper bound of the bucket that the `q`th quantile falls into, `q` is within `[0, 1]`.
+    ///
+    /// [None] if nothing is recorded.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        self.buckets()
+            .find(|(_, n)| {
+                seen += n;
+                seen >= rank
+            })
+            .map(|(bound, _)| bound)
+    }
+
+    /// Upper bounds of the buckets along with their counts, in increasing order.
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.buckets.iter().enumerate().map(|(i, n)| {
+            let bound = 1_u64.checked_shl(i as u32 + 1).unwrap_or(u64::MAX);
+            (Duration::from_nanos(bound), *n)
+        })
+    }
+}
+
+///
+/// Lock-free histogram that completions are recorded to.
+struct Histogram {
+    buckets: [AtomicU64; BUCKETS],
+    sum_ns: AtomicU64,
+}
+
+impl Histogram {
+    fn new() -> Histogram {
+        Histogram {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            sum_ns: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, latency: Duration) {
+        let ns = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+        let bucket = (u64::BITS - 1 - ns.max(1).leading_zeros()) as usize;
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_ns.fetch_add(ns, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> LatencyHistogram {
+        LatencyHistogram {
+            buckets: std::array::from_fn(|i| self.buckets[i].load(Ordering::Relaxed)),
+            sum_ns: self.sum_ns.load(Ordering::Relaxed),
+        }
+    }
+}
+
+///
+/// Completion latencies of a proactor, per operation class.
+pub(crate) struct Latencies([Histogram; OpClass::ALL.len()]);
+
+impl Latencies {
+    pub(crate) fn new() -> Latencies {
+        Latencies(std::array::from_fn(|_| Histogram::new()))
+    }
+
+    pub(crate) fn record(&self, class: OpClass, latency: Duration) {
+        self.0[class as usize].record(latency);
+    }
+
+    pub(crate) fn histogram(&self, class: OpClass) -> LatencyHistogram {
+        self.0[class as usize].snapshot()
+    }
+}
@@ -34,10 +34,25 @@
 mod async_io;
 /// Nuclei's configuration options reside here.
 pub mod config;
+mod driver;
+mod error;
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "illumos"))]
+mod fixed;
 mod handle;
+mod hook;
+/// Kernel-side copies between handles, through `splice` and `tee`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod io;
+mod latency;
 mod proactor;
+/// Thread-per-core runtime, where every worker owns its own proactor.
+#[cfg(target_os = "linux")]
+pub mod runtime;
 mod submission_handler;
 mod sys;
+/// Timers that are driven by the proactor.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "illumos"))]
+pub mod time;
 mod utils;
 mod waker;
 
@@ -81,6 +96,11 @@ mod syscore {
 }
 
 pub use async_global_executor::*;
+pub use error::NucleiError;
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "illumos"))]
+pub use fixed::{Acquire, FixedBuf, FixedBufferPool};
+pub use hook::{EventHook, OpEvent, OpEventKind};
+pub use latency::{LatencyHistogram, OpClass};
 pub use proactor::*;
 
 #[cfg(feature = "attributes")]
@@ -1,49 +1,231 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{future::Future, io};
 
 use crate::config::NucleiConfig;
+use crate::driver::Parker;
+use crate::error::NucleiError;
+use crate::latency::{LatencyHistogram, OpClass};
 use once_cell::sync::OnceCell;
+use pin_project_lite::pin_project;
 
 use super::syscore::*;
 use super::waker::*;
-use crate::spawn_blocking;
 use crate::sys::IoBackend;
 
 pub use super::handle::*;
 
 ///
 /// Concrete proactor instance
-pub struct Proactor(pub(crate) SysProactor);
+///
+/// Cloning a proactor gives another reference to the same instance.
+#[derive(Clone)]
+pub struct Proactor(pub(crate) Arc<SysProactor>);
 unsafe impl Send for Proactor {}
 unsafe impl Sync for Proactor {}
 
-static mut PROACTOR: OnceCell<Proactor> = OnceCell::new();
+impl PartialEq for Proactor {
+    /// Proactors are equal if they are the same instance.
+    fn eq(&self, other: &Proactor) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Proactor {}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "illumos"))]
+impl fmt::Debug for Proactor {
+    /// Prints the backend and the in-flight operations, see [Proactor::inflight].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+///
+/// Snapshot of the counters of a proactor, see [Proactor::stats].
+///
+/// Counters are cumulative since the proactor is built, the rest reflects the moment of the snapshot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProactorStats {
+    /// Submission queue entries handed to io_uring, including the internal ones like cancellations.
+    /// On epoll, interests registered for operations.
+    pub sqes_submitted: u64,
+    /// Completion queue entries reaped from io_uring. On epoll, events dispatched to interests.
+    pub cqes_reaped: u64,
+    /// Operations that are submitted and not completed yet.
+    pub inflight: u64,
+    /// Times a submission found the io_uring submission queue full.
+    pub sq_full_events: u64,
+    /// Completions that the kernel dropped because the io_uring completion queue was full.
+    pub cq_overflow: u64,
+    /// Times the proactor is woken.
+    pub wakeups: u64,
+    /// File descriptors registered to epoll, zero on io_uring.
+    pub registered_fds: u64,
+    /// File descriptors that have operations waiting on them in epoll, zero on io_uring.
+    pub completions: u64,
+}
+
+///
+/// Operation that is in flight on a proactor, see [Proactor::inflight].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InflightOperation {
+    /// Id of the operation, the `user_data` of the submission on io_uring.
+    /// It is the same as [OpEvent::user_data](crate::OpEvent::user_data).
+    pub id: u64,
+    /// io_uring opcode of the operation, [None] on epoll.
+    pub opcode: Option<u8>,
+    /// Class of the operation, [None] for operations that aren't classified, like timers.
+    pub class: Option<OpClass>,
+    /// File descriptor the operation is on, `-1` if it isn't on one.
+    pub fd: i32,
+    /// epoll events that the operation waits for, zero on io_uring.
+    pub events: u32,
+    /// Whether the cancellation of the operation is requested and not acknowledged yet.
+    pub cancelled: bool,
+    /// When the operation is submitted.
+    pub submitted_at: Instant,
+    /// Time passed since the submission, as of the moment of the dump.
+    pub age: Duration,
+}
+
+///
+/// Operations that the running kernel supports, see [Proactor::capabilities].
+///
+/// io_uring support is probed with `IORING_REGISTER_PROBE` when the proactor is built, buffer
+/// rings and file tables by registering them.
+/// Everything is reported as unsupported on epoll, where operations are readiness-based.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Multishot accept (5.19+), [Handle::accept_multi] falls back to single-shot accepts without it.
+    ///
+    /// It isn't probed, it is inferred from the opcodes of the same kernel release, which might
+    /// not hold on backported kernels.
+    pub accept_multi: bool,
+    /// Multishot receive (6.0+), inferred the same way as `accept_multi`.
+    pub recv_multi: bool,
+    /// Zero-copy send (6.0+).
+    pub send_zc: bool,
+    /// Zero-copy `sendmsg` (6.1+).
+    pub sendmsg_zc: bool,
+    /// Creating sockets through the ring (5.19+).
+    pub socket: bool,
+    /// Provided buffer rings (5.19+).
+    pub buf_ring: bool,
+    /// Direct descriptors in slots that the kernel allocates (5.19+), see [Handle::register_direct].
+    pub direct_files: bool,
+    /// Provided buffers, the predecessor of buffer rings (5.7+).
+    pub provide_buffers: bool,
+    /// `splice` (5.7+).
+    pub splice: bool,
+    /// `tee` (5.8+).
+    pub tee: bool,
+    /// Internal polling of the operations that would block, instead of handing them to workers (5.7+).
+    pub fast_poll: bool,
+    /// Timeouts on waiting for completions (5.11+).
+    pub ext_arg: bool,
+}
+
+///
+/// How [Proactor::shutdown] treats the operations that are still in flight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShutdownMode {
+    /// Waits up to the given grace period for in-flight operations to complete, operations that
+    /// are still in flight afterwards are cancelled. [Duration::MAX] waits for all of them.
+    Drain(Duration),
+    /// Cancels the in-flight operations right away, they complete with `ECANCELED`.
+    Cancel,
+}
+
+static PROACTOR: OnceCell<Proactor> = OnceCell::new();
+
+thread_local! {
+    /// Proactor that the thread has entered, see [Proactor::enter].
+    static CURRENT: RefCell<Option<Proactor>> = const { RefCell::new(None) };
+}
 
 impl Proactor {
-    /// Returns a reference to the proactor.
+    /// Returns a reference to the process-wide proactor.
+    ///
+    /// Panics if the proactor can't be built, see [Proactor::try_get].
     pub fn get() -> &'static Proactor {
-        unsafe {
-            PROACTOR.get_or_init(|| {
-                Proactor(
-                    SysProactor::new(NucleiConfig::default())
-                        .expect("cannot initialize IO backend"),
-                )
-            })
-        }
+        PROACTOR.get_or_init(|| {
+            Proactor::new(NucleiConfig::default()).expect("cannot initialize IO backend")
+        })
+    }
+
+    /// Returns a reference to the process-wide proactor, building it with the default config
+    /// if it isn't built yet.
+    pub fn try_get() -> Result<&'static Proactor, NucleiError> {
+        PROACTOR.get_or_try_init(|| Ok(Proactor::new(NucleiConfig::default())?))
     }
 
-    /// Builds a proactor instance with given config and returns a reference to it.
+    /// Builds an independent proactor instance with its own rings and in-flight operations.
+    ///
+    /// The instance is used by the threads that [enter](Proactor::enter) it, which is how
+    /// thread-per-core servers avoid contending on a single proactor.
+    pub fn new(config: NucleiConfig) -> io::Result<Proactor> {
+        Ok(Proactor(Arc::new(SysProactor::new(config)?)))
+    }
+
+    /// Builds the process-wide proactor instance with given config and returns a reference to it.
+    ///
+    /// Panics if the proactor can't be built, see [Proactor::try_with_config].
     pub fn with_config(config: NucleiConfig) -> &'static Proactor {
-        unsafe {
-            let proactor =
-                Proactor(SysProactor::new(config.clone()).expect("cannot initialize IO backend"));
-            PROACTOR
-                .set(proactor)
-                .map_err(|e| "Proactor instance not being able to set.")
-                .unwrap();
-
-            PROACTOR.wait()
+        match Proactor::try_with_config(config) {
+            Ok(proactor) => proactor,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Builds the process-wide proactor instance with given config and returns a reference to it.
+    ///
+    /// Fails with [NucleiError::AlreadyInitialized] if the process-wide proactor is already built,
+    /// either by an earlier call or implicitly by [Proactor::get].
+    pub fn try_with_config(config: NucleiConfig) -> Result<&'static Proactor, NucleiError> {
+        if PROACTOR.get().is_some() {
+            return Err(NucleiError::AlreadyInitialized);
+        }
+
+        let proactor = Proactor::new(config)?;
+        PROACTOR
+            .set(proactor)
+            .map_err(|_| NucleiError::AlreadyInitialized)?;
+
+        Ok(PROACTOR.wait())
+    }
+
+    /// Returns the proactor the current thread has entered, or the process-wide one if it hasn't.
+    pub fn current() -> Proactor {
+        CURRENT
+            .with(|current| current.borrow().clone())
+            .unwrap_or_else(|| Proactor::get().clone())
+    }
+
+    /// Binds this proactor to the current thread until the returned guard is dropped.
+    ///
+    /// Handles that are created on the thread are owned by this proactor, and their operations
+    /// are submitted to it regardless of the thread they are polled from.
+    pub fn enter(&self) -> EnterGuard {
+        let prev = CURRENT.with(|current| current.replace(Some(self.clone())));
+
+        EnterGuard {
+            prev,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Polls the future with this proactor entered, so its operations are submitted to it.
+    pub(crate) fn route<F: Future>(&self, future: F) -> Routed<F> {
+        Routed {
+            proactor: self.clone(),
+            future,
         }
     }
 
@@ -57,9 +239,73 @@ impl Proactor {
         self.0.wait(max_event_size, duration)
     }
 
+    /// Waits for completions on behalf of a parked thread, which blocks until there are some.
+    pub(crate) fn wait_parked(&self, max_event_size: usize) -> io::Result<usize> {
+        self.0.wait_parked(max_event_size)
+    }
+
     /// Get the IO backend that is used with Nuclei's proactor.
+    ///
+    /// This is the backend that is selected at runtime, which might differ from the requested one
+    /// if the backend selection is left to probing.
     pub fn backend() -> IoBackend {
-        BACKEND
+        Proactor::current().0.backend()
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "illumos"))]
+    /// Shuts the proactor down.
+    ///
+    /// New operations fail with [io::ErrorKind::NotConnected] from now on, in-flight ones are
+    /// drained or cancelled according to `mode`. This returns once nothing is in flight, and
+    /// threads waiting on the proactor, like the driver of [drive], are released.
+    ///
+    /// The io_uring ring or the epoll fd is closed when the last reference to the proactor is
+    /// dropped, which includes the handles that are created on it.
+    pub fn shutdown(&self, mode: ShutdownMode) -> io::Result<()> {
+        self.0.shutdown(mode)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "illumos"))]
+    /// Snapshot of the proactor counters, meant to be exported to monitoring systems.
+    pub fn stats(&self) -> ProactorStats {
+        self.0.stats()
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "illumos"))]
+    /// Operations that the running kernel supports through the proactor's backend.
+    pub fn capabilities(&self) -> Capabilities {
+        self.0.capabilities()
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "illumos"))]
+    /// Operations that are in flight, oldest first.
+    ///
+    /// Meant for finding the IO that a stuck service waits on, the [Debug](fmt::Debug) output of
+    /// the proactor prints the same. On epoll, these are the interests waiting for readiness.
+    pub fn inflight(&self) -> Vec<InflightOperation> {
+        self.0.inflight()
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "illumos"))]
+    /// Submit-to-completion latency distribution of the operation class.
+    ///
+    /// On io_uring, latency is measured from pushing the submission to dispatching its completion.
+    /// On epoll, from registering the interest to dispatching the readiness event, file operations
+    /// are not recorded since they don't go through epoll.
+    pub fn latency(&self, class: OpClass) -> LatencyHistogram {
+        self.0.latency(class)
+    }
+
+    /// Whether the proactor is shut down, see [Proactor::shutdown].
+    pub fn is_shutdown(&self) -> bool {
+        #[cfg(any(target_os = "linux", target_os = "android", target_os = "illumos"))]
+        {
+            self.0.is_shutdown()
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "illumos")))]
+        {
+            false
+        }
     }
 
     /// Get underlying proactor instance.
@@ -68,44 +314,121 @@ impl Proactor {
     }
 
     #[cfg(all(feature = "iouring", target_os = "linux"))]
-    /// Get IO_URING backend probes
-    pub fn ring_params(&self) -> &rustix_uring::Parameters {
-        unsafe { IO_URING.as_ref().unwrap().params() }
+    /// Get IO_URING backend probes, [None] if io_uring backend isn't in use.
+    pub fn ring_params(&self) -> Option<&rustix_uring::Parameters> {
+        match self.inner() {
+            SysProactor::IoUring(p) => Some(p.params()),
+            _ => None,
+        }
+    }
+
+    #[cfg(all(feature = "iouring", target_os = "linux"))]
+    /// File descriptor of the io_uring, [None] if io_uring backend isn't in use.
+    ///
+    /// Other proactors can share the kernel worker pool of this one through
+    /// [IoUringConfiguration::attach_wq](crate::config::IoUringConfiguration::attach_wq).
+    pub fn ring_fd(&self) -> Option<std::os::unix::io::RawFd> {
+        match self.inner() {
+            SysProactor::IoUring(p) => Some(p.ring_fd()),
+            _ => None,
+        }
+    }
+
+    #[cfg(all(feature = "iouring", target_os = "linux"))]
+    /// Number of times a submission found the io_uring submission queue full.
+    ///
+    /// Each occurrence has been handled by the configured
+    /// [SubmissionBackpressure](crate::config::SubmissionBackpressure) policy.
+    pub fn sq_full_events(&self) -> u64 {
+        self.0.sq_full_events()
     }
 }
 
 ///
-/// IO driver that drives underlying event systems
+/// Keeps a proactor bound to the thread, previously entered proactor is restored when dropped.
+pub struct EnterGuard {
+    prev: Option<Proactor>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for EnterGuard {
+    fn drop(&mut self) {
+        let prev = self.prev.take();
+        CURRENT.with(|current| *current.borrow_mut() = prev);
+    }
+}
+
+pin_project! {
+    ///
+    /// Future that enters its proactor every time it is polled.
+    pub(crate) struct Routed<F> {
+        proactor: Proactor,
+        #[pin]
+        future: F,
+    }
+}
+
+impl<F: Future> Future for Routed<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _guard = this.proactor.enter();
+        this.future.poll(cx)
+    }
+}
+
+///
+/// Backend of the current proactor, borrowed for the duration of an operation.
+pub(crate) struct Current<T: 'static> {
+    proactor: Proactor,
+    backend: fn(&SysProactor) -> &T,
+}
+
+impl<T> Current<T> {
+    pub(crate) fn new(backend: fn(&SysProactor) -> &T) -> Current<T> {
+        Current {
+            proactor: Proactor::current(),
+            backend,
+        }
+    }
+}
+
+impl<T> Deref for Current<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        (self.backend)(self.proactor.inner())
+    }
+}
+
+///
+/// IO driver that drives underlying event systems of the current proactor
+///
+/// Blocks the calling thread on the future, the thread is parked until the future's waker fires.
+/// Meanwhile the thread waits on the proactor for completions, unless another thread already
+/// does, in which case it takes over once that thread is done.
 pub fn drive<T>(future: impl Future<Output = T>) -> T {
-    let p = Proactor::get();
-    let waker = waker_fn(move || {
-        p.wake();
-    });
+    let parker = Arc::new(Parker::new(Proactor::current()));
+    let waker = {
+        let parker = parker.clone();
+        waker_fn(move || parker.unpark())
+    };
 
     let cx = &mut Context::from_waker(&waker);
     futures::pin_mut!(future);
 
-    let driver = spawn_blocking(move || loop {
-        let _ = p.wait(1, None);
-    });
-
-    futures::pin_mut!(driver);
-
     loop {
         if let Poll::Ready(val) = future.as_mut().poll(cx) {
             return val;
         }
 
-        cx.waker().wake_by_ref();
-
-        // TODO: (vcq): we don't need this.
-        // let _duration = Duration::from_millis(1);
-        let _ = driver.as_mut().poll(cx);
+        parker.park();
     }
 }
 
 #[cfg(test)]
-#[cfg(target_os = "linux")]
+#[cfg(all(feature = "iouring", target_os = "linux"))]
 mod proactor_tests {
     use crate::config::{IoUringConfiguration, NucleiConfig};
     use crate::Proactor;
@@ -115,7 +438,7 @@ mod proactor_tests {
     fn proactor_with_defaults() {
         let old = Proactor::get();
 
-        let osq = old.0.sq.lock();
+        let osq = old.0.uring().sq.lock();
         let olen = osq.capacity();
         drop(osq);
         dbg!(olen);
@@ -133,15 +456,16 @@ mod proactor_tests {
                 per_numa_unbounded_worker_count: Some(13),
                 ..IoUringConfiguration::default()
             },
+            ..NucleiConfig::default()
         };
         let new = Proactor::with_config(config);
         let old = Proactor::get();
 
-        let nsq = new.0.sq.lock();
+        let nsq = new.0.uring().sq.lock();
         let nlen = nsq.capacity();
         drop(nsq);
 
-        let osq = old.0.sq.lock();
+        let osq = old.0.uring().sq.lock();
         let olen = osq.capacity();
         drop(osq);
 
@@ -0,0 +1,508 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::io::{self, Write};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, ThreadId};
+use std::{fmt, mem};
+
+use futures::channel::oneshot;
+use futures::AsyncReadExt;
+use lever::sync::prelude::*;
+use once_cell::sync::OnceCell;
+
+use crate::config::NucleiConfig;
+use crate::waker::waker_fn;
+use crate::{Handle, Proactor};
+
+macro_rules! syscall {
+    ($fn:ident $args:tt) => {{
+        let res = unsafe { libc::$fn $args };
+        if res == -1 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(res)
+        }
+    }};
+}
+
+/// Completions that are reaped by a worker in one go.
+const MAX_EVENTS: usize = 64;
+
+///
+/// Builds a thread-per-core [Runtime].
+///
+/// Every worker thread owns an independent [Proactor] built from the given config and runs a
+/// single-threaded executor on top of it, so tasks and their IO never leave the worker.
+pub struct Builder {
+    workers: usize,
+    pin_to_cpus: bool,
+    config: NucleiConfig,
+    thread_name: String,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            workers: thread::available_parallelism().map_or(1, |n| n.get()),
+            pin_to_cpus: false,
+            config: NucleiConfig::default(),
+            thread_name: "nuclei-worker".into(),
+        }
+    }
+}
+
+impl Builder {
+    ///
+    /// Builder with one worker per available CPU.
+    pub fn new() -> Builder {
+        Builder::default()
+    }
+
+    ///
+    /// Number of worker threads to start.
+    ///
+    /// **[default]**: number of CPUs available to the process.
+    pub fn worker_threads(mut self, workers: usize) -> Builder {
+        self.workers = workers;
+        self
+    }
+
+    ///
+    /// Pins every worker to a CPU with `sched_setaffinity`.
+    ///
+    /// Workers are assigned to the CPUs the process is allowed to run on in order, and wrap
+    /// around if there are more workers than CPUs.
+    ///
+    /// **[default]**: `false`
+    pub fn pin_to_cpus(mut self, pin: bool) -> Builder {
+        self.pin_to_cpus = pin;
+        self
+    }
+
+    ///
+    /// Config of the proactor that every worker builds for itself.
+    pub fn config(mut self, config: NucleiConfig) -> Builder {
+        self.config = config;
+        self
+    }
+
+    ///
+    /// Prefix of worker thread names, worker index is appended to it.
+    ///
+    /// **[default]**: `nuclei-worker`
+    pub fn thread_name(mut self, name: impl Into<String>) -> Builder {
+        self.thread_name = name.into();
+        self
+    }
+
+    ///
+    /// Starts the workers, returns once all of them set up their proactor.
+    pub fn build(self) -> io::Result<Runtime> {
+        if self.workers == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "runtime needs at least one worker thread",
+            ));
+        }
+
+        let cpus = if self.pin_to_cpus {
+            allowed_cpus()?
+        } else {
+            Vec::new()
+        };
+
+        let mut runtime = Runtime {
+            workers: Vec::with_capacity(self.workers),
+            next: AtomicUsize::new(0),
+        };
+
+        for index in 0..self.workers {
+            let (notify, mailbox) = UnixStream::pair()?;
+            notify.set_nonblocking(true)?;
+            mailbox.set_nonblocking(true)?;
+
+            let remote = Arc::new(Remote {
+                thread: OnceCell::new(),
+                woken: TTas::new(VecDeque::new()),
+                injected: TTas::new(VecDeque::new()),
+                notify,
+                shutdown: AtomicBool::new(false),
+            });
+            let cpu = (!cpus.is_empty()).then(|| cpus[index % cpus.len()]);
+            let config = self.config.clone();
+            let (ready_tx, ready_rx) = crossbeam_channel::bounded(1);
+
+            let thread = thread::Builder::new()
+                .name(format!("{}-{}", self.thread_name, index))
+                .spawn({
+                    let remote = remote.clone();
+                    move || run_worker(remote, mailbox, config, cpu, ready_tx)
+                })?;
+
+            // Workers that are already started are shut down by the runtime on failure.
+            runtime.workers.push(Worker {
+                remote,
+                thread: Some(thread),
+            });
+            ready_rx
+                .recv()
+                .map_err(|_| io::Error::other("runtime worker exited during startup"))??;
+        }
+
+        Ok(runtime)
+    }
+}
+
+///
+/// Thread-per-core runtime, see [Builder].
+///
+/// Workers are shut down and joined when the runtime is dropped, tasks that haven't completed by
+/// then are dropped on their worker.
+pub struct Runtime {
+    workers: Vec<Worker>,
+    next: AtomicUsize,
+}
+
+struct Worker {
+    remote: Arc<Remote>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Runtime {
+    ///
+    /// Number of worker threads.
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    ///
+    /// Spawns the future on the given worker.
+    ///
+    /// # Panics
+    ///
+    /// If `worker` is not less than [Runtime::workers].
+    pub fn spawn_on<F>(&self, worker: usize, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.workers[worker].remote.inject(move || {
+            spawn_detached(async move {
+                let _ = tx.send(future.await);
+            })
+        });
+
+        JoinHandle { rx }
+    }
+
+    ///
+    /// Spawns the future on the next worker in round-robin order.
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawn_on(self.next_worker(), future)
+    }
+
+    ///
+    /// Hands the IO object over to the next worker in round-robin order, and runs `f` with it
+    /// there.
+    ///
+    /// This is how accepted connections are spread across workers: the [Handle] is created on
+    /// the worker, so its operations are submitted to the worker's own proactor. The future
+    /// returned by `f` doesn't need to be [Send].
+    pub fn distribute<T, F, Fut, R>(&self, io: T, f: F) -> JoinHandle<io::Result<R>>
+    where
+        T: AsRawFd + Send + 'static,
+        F: FnOnce(Handle<T>) -> Fut + Send + 'static,
+        Fut: Future<Output = io::Result<R>> + 'static,
+        R: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.workers[self.next_worker()].remote.inject(move || {
+            spawn_detached(async move {
+                let res = match Handle::new(io) {
+                    Ok(handle) => f(handle).await,
+                    Err(e) => Err(e),
+                };
+                let _ = tx.send(res);
+            })
+        });
+
+        JoinHandle { rx }
+    }
+
+    ///
+    /// Runs the future on the next worker, and blocks the current thread until it completes.
+    ///
+    /// # Panics
+    ///
+    /// If the worker panics while running it, or if it is called from a worker, which would block
+    /// the ring that the future waits on.
+    pub fn block_on<F>(&self, future: F) -> F::Output
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        if EXECUTOR.with(|current| current.borrow().is_some()) {
+            panic!(
+                "nuclei: cannot block on a future from a runtime worker, \
+                 it would block the worker that drives the future; await it instead"
+            );
+        }
+
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        self.workers[self.next_worker()].remote.inject(move || {
+            spawn_detached(async move {
+                let _ = tx.send(future.await);
+            })
+        });
+
+        rx.recv()
+            .expect("runtime worker stopped before completing the future")
+    }
+
+    fn next_worker(&self) -> usize {
+        self.next.fetch_add(1, Ordering::Relaxed) % self.workers.len()
+    }
+}
+
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        for worker in self.workers.iter() {
+            worker.remote.shutdown.store(true, Ordering::Release);
+            worker.remote.notify();
+        }
+
+        for worker in self.workers.iter_mut() {
+            if let Some(thread) = worker.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Runtime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Runtime")
+            .field("workers", &self.workers.len())
+            .finish()
+    }
+}
+
+///
+/// Spawns a future on the worker of the current thread.
+///
+/// The future is polled only by this worker, so it doesn't need to be [Send].
+///
+/// # Panics
+///
+/// If the current thread is not a [Runtime] worker.
+pub fn spawn_local<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + 'static,
+    F::Output: 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    spawn_detached(async move {
+        let _ = tx.send(future.await);
+    });
+
+    JoinHandle { rx }
+}
+
+fn spawn_detached(future: impl Future<Output = ()> + 'static) {
+    EXECUTOR
+        .with(|executor| executor.borrow().clone())
+        .expect("spawn_local called outside of a runtime worker")
+        .spawn(future);
+}
+
+///
+/// Output of a spawned task.
+///
+/// Dropping the handle detaches the task, it keeps running on its worker.
+pub struct JoinHandle<T> {
+    rx: oneshot::Receiver<T>,
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = io::Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.rx)
+            .poll(cx)
+            .map_err(|_| io::Error::other("task was dropped before it completed"))
+    }
+}
+
+///
+/// Part of a worker that is shared with the other threads.
+struct Remote {
+    thread: OnceCell<ThreadId>,
+    woken: TTas<VecDeque<usize>>,
+    injected: TTas<VecDeque<Box<dyn FnOnce() + Send>>>,
+    /// Written to wake the worker up, the worker keeps a read in flight on the other end.
+    notify: UnixStream,
+    shutdown: AtomicBool,
+}
+
+impl Remote {
+    fn wake(&self, task: usize) {
+        self.woken.lock().push_back(task);
+        self.notify();
+    }
+
+    fn inject(&self, f: impl FnOnce() + Send + 'static) {
+        self.injected.lock().push_back(Box::new(f));
+        self.notify();
+    }
+
+    fn notify(&self) {
+        // The worker drains its queues before it waits on the proactor.
+        if self.thread.get() == Some(&thread::current().id()) {
+            return;
+        }
+
+        // A full socket already has a pending wakeup.
+        let _ = (&self.notify).write(&[1]);
+    }
+}
+
+thread_local! {
+    static EXECUTOR: RefCell<Option<Rc<Executor>>> = const { RefCell::new(None) };
+}
+
+struct Task {
+    future: Pin<Box<dyn Future<Output = ()>>>,
+    waker: Waker,
+}
+
+///
+/// Single-threaded executor of a worker.
+struct Executor {
+    remote: Arc<Remote>,
+    tasks: RefCell<HashMap<usize, Task>>,
+    next_id: Cell<usize>,
+}
+
+impl Executor {
+    fn spawn(&self, future: impl Future<Output = ()> + 'static) {
+        let id = self.next_id.get();
+        self.next_id.set(id.wrapping_add(1));
+
+        let remote = self.remote.clone();
+        let task = Task {
+            future: Box::pin(future),
+            waker: waker_fn(move || remote.wake(id)),
+        };
+        self.tasks.borrow_mut().insert(id, task);
+        self.remote.woken.lock().push_back(id);
+    }
+
+    fn poll(&self, id: usize) {
+        // Task is taken out while it is polled, so it can spawn other tasks.
+        let Some(mut task) = self.tasks.borrow_mut().remove(&id) else {
+            return;
+        };
+
+        let cx = &mut Context::from_waker(&task.waker);
+        if task.future.as_mut().poll(cx).is_pending() {
+            self.tasks.borrow_mut().insert(id, task);
+        }
+    }
+}
+
+fn run_worker(
+    remote: Arc<Remote>,
+    mailbox: UnixStream,
+    config: NucleiConfig,
+    cpu: Option<usize>,
+    ready: crossbeam_channel::Sender<io::Result<()>>,
+) {
+    let proactor = match cpu
+        .map_or(Ok(()), pin_to_cpu)
+        .and_then(|_| Proactor::new(config))
+    {
+        Ok(proactor) => proactor,
+        Err(e) => {
+            let _ = ready.send(Err(e));
+            return;
+        }
+    };
+    let _guard = proactor.enter();
+    let _ = remote.thread.set(thread::current().id());
+
+    let executor = Rc::new(Executor {
+        remote: remote.clone(),
+        tasks: RefCell::new(HashMap::new()),
+        next_id: Cell::new(0),
+    });
+    EXECUTOR.with(|current| *current.borrow_mut() = Some(executor.clone()));
+
+    // Keeps a read in flight, so wakeups from other threads complete the wait on the proactor.
+    let mut mailbox = match Handle::new(mailbox) {
+        Ok(mailbox) => mailbox,
+        Err(e) => {
+            let _ = ready.send(Err(e));
+            return;
+        }
+    };
+    executor.spawn(async move {
+        let mut buf = [0; 64];
+        while let Ok(1..) = mailbox.read(&mut buf).await {}
+    });
+    let _ = ready.send(Ok(()));
+
+    while !remote.shutdown.load(Ordering::Acquire) && !proactor.is_shutdown() {
+        let injected = mem::take(&mut *remote.injected.lock());
+        injected.into_iter().for_each(|f| f());
+
+        let woken = mem::take(&mut *remote.woken.lock());
+        if woken.is_empty() {
+            let _ = proactor.wait(MAX_EVENTS, None);
+            continue;
+        }
+
+        woken.into_iter().for_each(|id| executor.poll(id));
+    }
+
+    // Tasks are dropped while the proactor is entered, so their operations are cancelled on it.
+    EXECUTOR.with(|current| current.borrow_mut().take());
+    let tasks = mem::take(&mut *executor.tasks.borrow_mut());
+    drop(tasks);
+}
+
+fn allowed_cpus() -> io::Result<Vec<usize>> {
+    let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+    syscall!(sched_getaffinity(
+        0,
+        mem::size_of::<libc::cpu_set_t>(),
+        &mut set
+    ))?;
+
+    Ok((0..libc::CPU_SETSIZE as usize)
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+        .collect())
+}
+
+fn pin_to_cpu(cpu: usize) -> io::Result<()> {
+    let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+    unsafe { libc::CPU_SET(cpu, &mut set) };
+    syscall!(sched_setaffinity(
+        0,
+        mem::size_of::<libc::cpu_set_t>(),
+        &set
+    ))?;
+
+    Ok(())
+}
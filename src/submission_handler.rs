@@ -1,10 +1,13 @@
-use super::handle::HandleOpRegisterer;
+use super::handle::{AsyncOp, HandleOpRegisterer};
+use crate::Proactor;
 
+use lever::prelude::*;
 use std::marker::PhantomData as marker;
 use std::{
     future::Future,
     io,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
@@ -14,17 +17,24 @@ where
 
 impl<T> SubmissionHandler<T>
 where
-    T: Unpin + HandleOpRegisterer,
+    T: Unpin,
 {
-    pub fn handle_read(
-        handle: Pin<&mut T>,
+    ///
+    /// Polls the operation kept in the given registerer slot, submitting the `completion_dispatcher`
+    /// if nothing is in flight. The slot keeps the in-flight operation alive between polls,
+    /// so a pending operation is never resubmitted.
+    ///
+    /// Operation is polled with the given `proactor` entered, so it is submitted to the proactor
+    /// that owns the handle.
+    pub fn handle_op(
+        registerer: Arc<TTas<Option<AsyncOp<usize>>>>,
+        proactor: &Proactor,
         cx: &mut Context,
         completion_dispatcher: impl Future<Output = io::Result<usize>> + 'static,
     ) -> Poll<io::Result<usize>> {
-        let handle = handle.get_mut();
-        let read_result = handle.read_registerer();
+        let _guard = proactor.enter();
 
-        let mut result = match read_result.try_lock() {
+        let mut result = match registerer.try_lock() {
             Some(result) => result,
             None => return Poll::Pending,
         };
@@ -44,34 +54,38 @@ where
 
         poll
     }
+}
 
-    pub fn handle_write(
+impl<T> SubmissionHandler<T>
+where
+    T: Unpin + HandleOpRegisterer,
+{
+    pub fn handle_read(
         handle: Pin<&mut T>,
         cx: &mut Context,
         completion_dispatcher: impl Future<Output = io::Result<usize>> + 'static,
     ) -> Poll<io::Result<usize>> {
         let handle = handle.get_mut();
-        let write_result = handle.write_registerer();
-
-        let mut result = match write_result.try_lock() {
-            Some(result) => result,
-            None => return Poll::Pending,
-        };
-
-        if result.is_none() {
-            *result = Some(Box::pin(completion_dispatcher));
-        }
-
-        let poll = {
-            let result = result.as_mut().unwrap();
-            result.as_mut().poll(cx).map_ok(|s| s)
-        };
-
-        if poll.is_ready() {
-            *result = None;
-        }
+        Self::handle_op(
+            handle.read_registerer(),
+            handle.proactor(),
+            cx,
+            completion_dispatcher,
+        )
+    }
 
-        poll
+    pub fn handle_write(
+        handle: Pin<&mut T>,
+        cx: &mut Context,
+        completion_dispatcher: impl Future<Output = io::Result<usize>> + 'static,
+    ) -> Poll<io::Result<usize>> {
+        let handle = handle.get_mut();
+        Self::handle_op(
+            handle.write_registerer(),
+            handle.proactor(),
+            cx,
+            completion_dispatcher,
+        )
     }
 
     // pub fn handle_seek(
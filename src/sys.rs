@@ -1,5 +1,6 @@
 ///
 /// Backends that are possible to use with Nuclei
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum IoBackend {
     /// BSD-like backend
     Kqueue,
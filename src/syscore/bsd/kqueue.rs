@@ -1,4 +1,5 @@
 use crate::sys::event::{kevent_ts, kqueue, KEvent};
+use crate::sys::IoBackend;
 use ahash::{HashMap, HashMapExt};
 use futures::channel::oneshot;
 use lever::prelude::*;
@@ -26,6 +27,7 @@ macro_rules! syscall {
 ///////////////////
 
 use crate::config::NucleiConfig;
+use crate::driver::Driver;
 use socket2::SockAddr;
 use std::mem;
 use std::os::unix::net::SocketAddr as UnixSocketAddr;
@@ -149,6 +151,9 @@ pub struct SysProactor {
 
     /// Hashmap for holding interested concrete completion callbacks
     completions: TTas<HashMap<RawFd, CompletionList>>,
+
+    /// Slot of the thread that drives the proactor
+    driver: Driver,
 }
 
 impl SysProactor {
@@ -164,6 +169,7 @@ impl SysProactor {
             write_stream,
             registered: TTas::new(HashMap::new()),
             completions: TTas::new(HashMap::new()),
+            driver: Driver::new(),
         };
 
         let rs = proactor.read_stream.lock();
@@ -230,6 +236,10 @@ impl SysProactor {
         Ok(())
     }
 
+    pub(crate) fn wait_parked(&self, max_event_size: usize) -> io::Result<usize> {
+        self.wait(max_event_size, None)
+    }
+
     pub fn wait(&self, max_event_size: usize, timeout: Option<Duration>) -> io::Result<usize> {
         // dbg!("WAIT");
         let timeout = timeout.map(|t| libc::timespec {
@@ -287,6 +297,14 @@ impl SysProactor {
         Ok(res)
     }
 
+    pub(crate) fn backend(&self) -> IoBackend {
+        IoBackend::Kqueue
+    }
+
+    pub(crate) fn driver(&self) -> &Driver {
+        &self.driver
+    }
+
     pub(crate) fn wake(&self) -> io::Result<()> {
         // dbg!("WAKE");
         let _ = (&self.write_stream).write(&[1]);
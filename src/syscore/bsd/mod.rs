@@ -7,5 +7,3 @@ pub(crate) use fs::*;
 pub(crate) use kqueue::*;
 
 pub(crate) use processor::*;
-
-pub const BACKEND: crate::sys::IoBackend = crate::sys::IoBackend::Kqueue;
@@ -24,13 +24,16 @@ impl<T: AsRawFd> Handle<T> {
             store_file: None,
             read: Arc::new(TTas::new(None)),
             write: Arc::new(TTas::new(None)),
+            read_timeout: None,
+            write_timeout: None,
+            proactor: Proactor::current(),
         })
     }
 
     pub(crate) fn new_with_callback(io: T, evflags: usize) -> io::Result<Handle<T>> {
         let fd = io.as_raw_fd();
         let mut handle = Handle::new(io)?;
-        let register = Proactor::get().inner().register_io(fd, evflags)?;
+        let register = Proactor::current().inner().register_io(fd, evflags)?;
         handle.chan = Some(register);
         Ok(handle)
     }
@@ -66,15 +69,15 @@ impl Handle<TcpStream> {
     }
 
     pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
-        Processor::processor_send(self.get_ref(), buf).await
+        Processor::processor_send(self.get_ref(), buf, self.write_timeout).await
     }
 
     pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_recv(self.get_ref(), buf).await
+        Processor::processor_recv(self.get_ref(), buf, self.read_timeout).await
     }
 
     pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_peek(self.get_ref(), buf).await
+        Processor::processor_peek(self.get_ref(), buf, self.read_timeout).await
     }
 }
 
@@ -88,20 +91,22 @@ impl Handle<UdpSocket> {
     }
 
     pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
-        Processor::processor_send(self.get_ref(), buf).await
+        Processor::processor_send(self.get_ref(), buf, self.write_timeout).await
     }
 
     pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_recv(self.get_ref(), buf).await
+        Processor::processor_recv(self.get_ref(), buf, self.read_timeout).await
     }
 
     pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_peek(self.get_ref(), buf).await
+        Processor::processor_peek(self.get_ref(), buf, self.read_timeout).await
     }
 
     pub async fn send_to<A: ToSocketAddrs>(&self, buf: &[u8], addr: A) -> io::Result<usize> {
         match addr.to_socket_addrs()?.next() {
-            Some(addr) => Processor::processor_send_to(self.get_ref(), buf, addr).await,
+            Some(addr) => {
+                Processor::processor_send_to(self.get_ref(), buf, addr, self.write_timeout).await
+            }
             None => Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 "given addresses can't be parsed",
@@ -110,11 +115,11 @@ impl Handle<UdpSocket> {
     }
 
     pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
-        Processor::processor_recv_from(self.get_ref(), buf).await
+        Processor::processor_recv_from(self.get_ref(), buf, self.read_timeout).await
     }
 
     pub async fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
-        Processor::processor_peek_from(self.get_ref(), buf).await
+        Processor::processor_peek_from(self.get_ref(), buf, self.read_timeout).await
     }
 }
 
@@ -156,15 +161,15 @@ impl Handle<UnixStream> {
     }
 
     pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
-        Processor::processor_send(self.get_ref(), buf).await
+        Processor::processor_send(self.get_ref(), buf, self.write_timeout).await
     }
 
     pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_recv(self.get_ref(), buf).await
+        Processor::processor_recv(self.get_ref(), buf, self.read_timeout).await
     }
 
     pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_peek(self.get_ref(), buf).await
+        Processor::processor_peek(self.get_ref(), buf, self.read_timeout).await
     }
 }
 
@@ -184,26 +189,26 @@ impl Handle<UnixDatagram> {
     }
 
     pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
-        Processor::processor_send(self.get_ref(), buf).await
+        Processor::processor_send(self.get_ref(), buf, self.write_timeout).await
     }
 
     pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_recv(self.get_ref(), buf).await
+        Processor::processor_recv(self.get_ref(), buf, self.read_timeout).await
     }
 
     pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_peek(self.get_ref(), buf).await
+        Processor::processor_peek(self.get_ref(), buf, self.read_timeout).await
     }
 
     pub async fn send_to<P: AsRef<Path>>(&self, buf: &[u8], path: P) -> io::Result<usize> {
-        Processor::processor_send_to_unix(self.get_ref(), buf, path).await
+        Processor::processor_send_to_unix(self.get_ref(), buf, path, self.write_timeout).await
     }
 
     pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, UnixSocketAddr)> {
-        Processor::processor_recv_from_unix(self.get_ref(), buf).await
+        Processor::processor_recv_from_unix(self.get_ref(), buf, self.read_timeout).await
     }
 
     pub async fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, UnixSocketAddr)> {
-        Processor::processor_peek_from_unix(self.get_ref(), buf).await
+        Processor::processor_peek_from_unix(self.get_ref(), buf, self.read_timeout).await
     }
 }
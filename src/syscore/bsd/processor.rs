@@ -6,6 +6,7 @@ use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6, UdpSocket};
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::os::unix::net::{SocketAddr as UnixSocketAddr, UnixStream};
 use std::path::Path;
+use std::time::Duration;
 use std::{
     fs::File,
     mem::ManuallyDrop,
@@ -61,7 +62,12 @@ impl Processor {
     ///// Commonality of TcpStream, UdpSocket, UnixStream, UnixDatagram
     ///////////////////////////////////
 
-    pub(crate) async fn processor_send<R: AsRawFd>(socket: &R, buf: &[u8]) -> io::Result<usize> {
+    // NOTE: kqueue backend doesn't support timeouts yet, operations wait indefinitely.
+    pub(crate) async fn processor_send<R: AsRawFd>(
+        socket: &R,
+        buf: &[u8],
+        _timeout: Option<Duration>,
+    ) -> io::Result<usize> {
         let sock = unsafe { socket2::Socket::from_raw_fd(socket.as_raw_fd()) };
         let sock = ManuallyDrop::new(sock);
 
@@ -69,7 +75,7 @@ impl Processor {
         match sock.send(buf) {
             Ok(res) => Ok(res),
             Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
-                let cc = Proactor::get()
+                let cc = Proactor::current()
                     .inner()
                     .register_io(socket.as_raw_fd(), libc::EVFILT_WRITE as _)?;
                 let events = cc.await?;
@@ -84,11 +90,19 @@ impl Processor {
         }
     }
 
-    pub(crate) async fn processor_recv<R: AsRawFd>(sock: &R, buf: &mut [u8]) -> io::Result<usize> {
+    pub(crate) async fn processor_recv<R: AsRawFd>(
+        sock: &R,
+        buf: &mut [u8],
+        _timeout: Option<Duration>,
+    ) -> io::Result<usize> {
         Self::recv_with_flags(sock, buf, 0).await
     }
 
-    pub(crate) async fn processor_peek<R: AsRawFd>(sock: &R, buf: &mut [u8]) -> io::Result<usize> {
+    pub(crate) async fn processor_peek<R: AsRawFd>(
+        sock: &R,
+        buf: &mut [u8],
+        _timeout: Option<Duration>,
+    ) -> io::Result<usize> {
         Self::recv_with_flags(sock, buf, libc::MSG_PEEK as _).await
     }
 
@@ -104,7 +118,7 @@ impl Processor {
         match sock.recv_with_flags(buf, flags as _) {
             Ok(res) => Ok(res),
             Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
-                let cc = Proactor::get()
+                let cc = Proactor::current()
                     .inner()
                     .register_io(socket.as_raw_fd(), libc::EVFILT_READ as _)?;
                 let events = cc.await?;
@@ -195,7 +209,7 @@ impl Processor {
                 let res = match sock.connect(&addr.into()) {
                     Ok(res) => Ok(res),
                     Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
-                        let cc = Proactor::get().inner().register_io(
+                        let cc = Proactor::current().inner().register_io(
                             stream.as_raw_fd(),
                             (libc::EAGAIN | libc::EINPROGRESS) as _,
                         )?;
@@ -279,7 +293,7 @@ impl Processor {
         {
             Ok(res) => Ok(res),
             Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
-                let cc = Proactor::get()
+                let cc = Proactor::current()
                     .inner()
                     .register_io(listener.as_raw_fd(), libc::EVFILT_READ as _)?;
                 let events = cc.await?;
@@ -304,6 +318,7 @@ impl Processor {
         socket: &R,
         buf: &[u8],
         addr: SocketAddr,
+        _timeout: Option<Duration>,
     ) -> io::Result<usize> {
         Self::send_to_dest(socket, buf, &socket2::SockAddr::from(addr)).await
     }
@@ -320,7 +335,7 @@ impl Processor {
         match sock.send_to(buf, addr) {
             Ok(res) => Ok(res),
             Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
-                let cc = Proactor::get()
+                let cc = Proactor::current()
                     .inner()
                     .register_io(socket.as_raw_fd(), libc::EVFILT_READ as _)?;
                 let events = cc.await?;
@@ -338,6 +353,7 @@ impl Processor {
     pub(crate) async fn processor_recv_from<R: AsRawFd>(
         sock: &R,
         buf: &mut [u8],
+        _timeout: Option<Duration>,
     ) -> io::Result<(usize, SocketAddr)> {
         Self::recv_from_with_flags(sock, buf, 0)
             .await
@@ -347,6 +363,7 @@ impl Processor {
     pub(crate) async fn processor_peek_from<R: AsRawFd>(
         sock: &R,
         buf: &mut [u8],
+        _timeout: Option<Duration>,
     ) -> io::Result<(usize, SocketAddr)> {
         Self::recv_from_with_flags(sock, buf, libc::MSG_PEEK as _)
             .await
@@ -367,7 +384,7 @@ impl Processor {
         {
             Ok(res) => Ok(res),
             Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
-                let cc = Proactor::get()
+                let cc = Proactor::current()
                     .inner()
                     .register_io(socket.as_raw_fd(), libc::EVFILT_READ as _)?;
                 let events = cc.await?;
@@ -404,7 +421,7 @@ impl Processor {
         {
             Ok(res) => Ok(res),
             Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
-                let cc = Proactor::get()
+                let cc = Proactor::current()
                     .inner()
                     .register_io(socket.as_raw_fd(), libc::EVFILT_READ as _)?;
                 let events = cc.await?;
@@ -441,7 +458,7 @@ impl Processor {
         let res = match sock.connect(&sockaddr) {
             Ok(res) => Ok(res),
             Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
-                let cc = Proactor::get()
+                let cc = Proactor::current()
                     .inner()
                     .register_io(stream.as_raw_fd(), (libc::EAGAIN | libc::EINPROGRESS) as _)?;
                 let events = cc.await?;
@@ -462,6 +479,7 @@ impl Processor {
         socket: &R,
         buf: &[u8],
         path: P,
+        _timeout: Option<Duration>,
     ) -> io::Result<usize> {
         Self::send_to_dest(socket, buf, &socket2::SockAddr::unix(path)?).await
     }
@@ -469,6 +487,7 @@ impl Processor {
     pub(crate) async fn processor_recv_from_unix<R: AsRawFd>(
         socket: &R,
         buf: &mut [u8],
+        _timeout: Option<Duration>,
     ) -> io::Result<(usize, UnixSocketAddr)> {
         Self::recv_from_with_flags(socket, buf, 0)
             .await
@@ -478,6 +497,7 @@ impl Processor {
     pub(crate) async fn processor_peek_from_unix<R: AsRawFd>(
         socket: &R,
         buf: &mut [u8],
+        _timeout: Option<Duration>,
     ) -> io::Result<(usize, UnixSocketAddr)> {
         Self::recv_from_with_flags(socket, buf, libc::MSG_PEEK as _)
             .await
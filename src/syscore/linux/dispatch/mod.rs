@@ -0,0 +1,221 @@
+mod nethandle;
+mod processor;
+mod timer;
+
+use std::fmt;
+use std::io;
+use std::os::unix::io::RawFd;
+use std::time::Duration;
+
+use super::epoll;
+use super::iouring;
+use super::iouring::fs::cancellation::Cancellation;
+use crate::config::NucleiConfig;
+use crate::driver::Driver;
+use crate::latency::{LatencyHistogram, OpClass};
+use crate::proactor::{Capabilities, InflightOperation, ProactorStats, ShutdownMode};
+use crate::sys::IoBackend;
+
+pub(crate) use iouring::{CompletionChan, StoreFile};
+pub(crate) use processor::*;
+pub(crate) use timer::*;
+
+///
+/// Proactor that is backed by the IO backend selected at runtime.
+pub enum SysProactor {
+    /// io_uring backend
+    IoUring(Box<iouring::SysProactor>),
+    /// epoll backend
+    Epoll(Box<epoll::SysProactor>),
+}
+
+impl SysProactor {
+    pub(crate) fn new(config: NucleiConfig) -> io::Result<SysProactor> {
+        match config.backend {
+            Some(IoBackend::IoUring) => {
+                iouring::SysProactor::new(config).map(|p| SysProactor::IoUring(Box::new(p)))
+            }
+            Some(IoBackend::Epoll) => {
+                epoll::SysProactor::new(config).map(|p| SysProactor::Epoll(Box::new(p)))
+            }
+            Some(IoBackend::Kqueue) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "kqueue backend is not available on this platform",
+            )),
+            // Probe io_uring by setting up the ring, fall back to epoll only if it is unavailable.
+            // Errors of the configuration are reported, rather than running on another backend.
+            None => match iouring::SysProactor::new(config.clone()) {
+                Ok(p) => Ok(SysProactor::IoUring(Box::new(p))),
+                Err(e) if uring_unavailable(&e) => {
+                    epoll::SysProactor::new(config).map(|p| SysProactor::Epoll(Box::new(p)))
+                }
+                Err(e) => Err(e),
+            },
+        }
+    }
+
+    pub(crate) fn backend(&self) -> IoBackend {
+        match self {
+            SysProactor::IoUring(p) => p.backend(),
+            SysProactor::Epoll(p) => p.backend(),
+        }
+    }
+
+    pub(crate) fn wake(&self) -> io::Result<()> {
+        match self {
+            SysProactor::IoUring(p) => p.wake(),
+            SysProactor::Epoll(p) => p.wake(),
+        }
+    }
+
+    pub(crate) fn wait(
+        &self,
+        max_event_size: usize,
+        duration: Option<Duration>,
+    ) -> io::Result<usize> {
+        match self {
+            SysProactor::IoUring(p) => p.wait(max_event_size, duration, false),
+            SysProactor::Epoll(p) => p.wait(max_event_size, duration),
+        }
+    }
+
+    pub(crate) fn wait_parked(&self, max_event_size: usize) -> io::Result<usize> {
+        match self {
+            SysProactor::IoUring(p) => p.wait(max_event_size, None, true),
+            SysProactor::Epoll(p) => p.wait_parked(max_event_size),
+        }
+    }
+
+    pub(crate) fn shutdown(&self, mode: ShutdownMode) -> io::Result<()> {
+        match self {
+            SysProactor::IoUring(p) => p.shutdown(mode),
+            SysProactor::Epoll(p) => p.shutdown(mode),
+        }
+    }
+
+    pub(crate) fn is_shutdown(&self) -> bool {
+        match self {
+            SysProactor::IoUring(p) => p.is_shutdown(),
+            SysProactor::Epoll(p) => p.is_shutdown(),
+        }
+    }
+
+    pub(crate) fn stats(&self) -> ProactorStats {
+        match self {
+            SysProactor::IoUring(p) => p.stats(),
+            SysProactor::Epoll(p) => p.stats(),
+        }
+    }
+
+    pub(crate) fn capabilities(&self) -> Capabilities {
+        match self {
+            SysProactor::IoUring(p) => p.capabilities(),
+            SysProactor::Epoll(p) => p.capabilities(),
+        }
+    }
+
+    pub(crate) fn inflight(&self) -> Vec<InflightOperation> {
+        match self {
+            SysProactor::IoUring(p) => p.inflight(),
+            SysProactor::Epoll(p) => p.inflight(),
+        }
+    }
+
+    pub(crate) fn latency(&self, class: OpClass) -> LatencyHistogram {
+        match self {
+            SysProactor::IoUring(p) => p.latency(class),
+            SysProactor::Epoll(p) => p.latency(class),
+        }
+    }
+
+    pub(crate) fn sq_full_events(&self) -> u64 {
+        match self {
+            SysProactor::IoUring(p) => p.sq_full_events(),
+            // epoll has no submission queue.
+            SysProactor::Epoll(_) => 0,
+        }
+    }
+
+    pub(crate) fn register_buffers(&self, bufs: &[libc::iovec]) -> io::Result<()> {
+        match self {
+            SysProactor::IoUring(p) => p.register_buffers(bufs),
+            SysProactor::Epoll(p) => p.register_buffers(bufs),
+        }
+    }
+
+    pub(crate) fn unregister_buffers(&self) -> io::Result<()> {
+        match self {
+            SysProactor::IoUring(p) => p.unregister_buffers(),
+            SysProactor::Epoll(p) => p.unregister_buffers(),
+        }
+    }
+
+    pub(crate) fn close_direct(&self, fd: RawFd) {
+        match self {
+            SysProactor::IoUring(p) => p.close_direct(fd),
+            // Direct descriptors are only registered on io_uring.
+            SysProactor::Epoll(_) => {}
+        }
+    }
+
+    pub(crate) fn close_slot(&self, slot: u32) {
+        match self {
+            SysProactor::IoUring(p) => p.close_slot(slot),
+            SysProactor::Epoll(_) => {}
+        }
+    }
+
+    pub(crate) fn deregister_io(&self, fd: RawFd) {
+        match self {
+            // Interests aren't registered per fd on io_uring.
+            SysProactor::IoUring(_) => {}
+            SysProactor::Epoll(p) => p.deregister_io(fd),
+        }
+    }
+
+    pub(crate) fn release_after_cancelled(&self, resources: Cancellation) {
+        match self {
+            SysProactor::IoUring(p) => p.release_after_cancelled(resources),
+            // epoll operations never outlive their futures.
+            SysProactor::Epoll(_) => drop(resources),
+        }
+    }
+
+    pub(crate) fn driver(&self) -> &Driver {
+        match self {
+            SysProactor::IoUring(p) => p.driver(),
+            SysProactor::Epoll(p) => p.driver(),
+        }
+    }
+
+    /// Get the io_uring proactor, operations are only dispatched to it when it is selected.
+    pub(crate) fn uring(&self) -> &iouring::SysProactor {
+        match self {
+            SysProactor::IoUring(p) => p,
+            _ => unreachable!("nuclei: io_uring operation dispatched to another backend"),
+        }
+    }
+
+    /// Get the epoll proactor, operations are only dispatched to it when it is selected.
+    pub(crate) fn epoll(&self) -> &epoll::SysProactor {
+        match self {
+            SysProactor::Epoll(p) => p,
+            _ => unreachable!("nuclei: epoll operation dispatched to another backend"),
+        }
+    }
+}
+
+/// Whether the ring setup failed because io_uring is unsupported by the kernel, or disabled by
+/// seccomp or the `io_uring_disabled` sysctl. Unsupported setup flags are configuration errors.
+fn uring_unavailable(e: &io::Error) -> bool {
+    matches!(e.raw_os_error(), Some(libc::ENOSYS) | Some(libc::EPERM))
+}
+
+impl fmt::Debug for SysProactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SysProactor::IoUring(p) => p.fmt(f),
+            SysProactor::Epoll(p) => p.fmt(f),
+        }
+    }
+}
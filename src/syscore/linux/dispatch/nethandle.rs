@@ -0,0 +1,570 @@
+use std::io;
+use std::marker::Unpin;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use std::net::{TcpListener, TcpStream, UdpSocket};
+// Unix specifics
+use std::os::unix::net::{SocketAddr as UnixSocketAddr, UnixDatagram, UnixListener, UnixStream};
+
+use futures::Stream;
+use lever::sync::prelude::*;
+
+use super::{Processor, StoreFile};
+use crate::syscore::linux::iouring::net::multishot::{
+    DirectStreamGenerator, RecvStream, TcpStreamGenerator,
+};
+
+use crate::{Direct, Handle, Proactor};
+
+impl<T> Handle<T> {
+    fn with_proactor(io: T, store_file: Option<StoreFile>, proactor: Proactor) -> Handle<T> {
+        Handle {
+            io_task: Some(io),
+            chan: None,
+            store_file,
+            read: Arc::new(TTas::new(None)),
+            write: Arc::new(TTas::new(None)),
+            read_timeout: None,
+            write_timeout: None,
+            proactor,
+            direct: None,
+        }
+    }
+}
+
+impl<T: AsRawFd> Handle<T> {
+    pub fn new(io: T) -> io::Result<Handle<T>> {
+        let fd = io.as_raw_fd();
+
+        Ok(Handle::with_proactor(
+            io,
+            Some(StoreFile::new(fd)),
+            Proactor::current(),
+        ))
+    }
+
+    ///
+    /// Registers the descriptor to the file table of the ring, as a direct descriptor.
+    ///
+    /// Operations on the handle refer to the descriptor by its slot afterwards, which saves the
+    /// kernel from looking the descriptor up on every operation and lets SQPOLL kernels before
+    /// 5.11 operate on it. The slot is allocated by the kernel in the table that is sized by
+    /// [IoUringConfiguration::file_table_len](crate::config::IoUringConfiguration::file_table_len),
+    /// and it is closed when the handle is dropped. The descriptor itself stays usable as usual.
+    ///
+    /// Registering is idempotent and returns the slot of the descriptor. Fails with
+    /// [io::ErrorKind::Unsupported] on epoll and on kernels without allocated slots, see
+    /// [Proactor::capabilities].
+    pub async fn register_direct(&mut self) -> io::Result<u32> {
+        if let Some(slot) = self.direct_slot() {
+            return Ok(slot);
+        }
+        let fd = self.as_raw_fd();
+
+        let slot = self
+            .proactor
+            .route(Processor::processor_register_direct(&fd))
+            .await?;
+        self.direct = Some(fd);
+
+        Ok(slot)
+    }
+
+    ///
+    /// Slot of the descriptor in the file table of the ring, if it is registered as a direct
+    /// descriptor with [Handle::register_direct].
+    pub fn direct_slot(&self) -> Option<u32> {
+        let fd = self.direct?;
+        self.proactor.inner().uring().direct_slot(fd)
+    }
+}
+
+impl Handle<TcpListener> {
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Handle<TcpListener>> {
+        // TODO: (vertexclique): Migrate towards to using initial subscription with callback.
+        // Using `new_with_callback`.
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+
+        Handle::new(listener)
+    }
+
+    ///
+    /// Single-call accept
+    pub async fn accept(&self) -> io::Result<(Handle<TcpStream>, Option<SocketAddr>)> {
+        self.proactor
+            .route(Processor::processor_accept_tcp_listener(
+                self.get_ref(),
+                self.read_timeout,
+            ))
+            .await
+    }
+
+    ///
+    /// Multishot accept
+    ///
+    /// Where multishot accept isn't supported, see [Proactor::capabilities], streams are
+    /// accepted one at a time instead.
+    pub async fn accept_multi(&self) -> io::Result<TcpStreamGenerator> {
+        let _guard = self.proactor.enter();
+        TcpStreamGenerator::new(self.get_ref())
+    }
+
+    ///
+    /// Single-call accept, straight into the file table of the ring
+    ///
+    /// The accepted stream is a [Direct] descriptor, it is never installed to the descriptor
+    /// table of the process. Fails with [io::ErrorKind::Unsupported] on epoll and on kernels
+    /// without allocated slots, see [Proactor::capabilities].
+    pub async fn accept_direct(&self) -> io::Result<Handle<Direct>> {
+        let slot = self
+            .proactor
+            .route(Processor::processor_accept_direct(
+                self.get_ref(),
+                self.read_timeout,
+            ))
+            .await?;
+
+        Ok(Handle::direct(Direct {
+            slot,
+            proactor: self.proactor.clone(),
+        }))
+    }
+
+    ///
+    /// Multishot accept, straight into the file table of the ring
+    ///
+    /// Same as [Handle::accept_direct], there is no fallback to single-shot accepts.
+    pub async fn accept_multi_direct(&self) -> io::Result<DirectStreamGenerator> {
+        let _guard = self.proactor.enter();
+        DirectStreamGenerator::new(self.get_ref())
+    }
+
+    pub fn incoming(
+        &self,
+    ) -> impl Stream<Item = io::Result<Handle<TcpStream>>> + Send + Unpin + '_ {
+        Box::pin(futures::stream::unfold(
+            self,
+            |listener: &Handle<TcpListener>| async move {
+                let res = listener.accept().await.map(|(stream, _)| stream);
+                Some((res, listener))
+            },
+        ))
+    }
+}
+
+impl Handle<TcpStream> {
+    pub async fn connect<A: ToSocketAddrs>(sock_addrs: A) -> io::Result<Handle<TcpStream>> {
+        Processor::processor_connect(sock_addrs, |addr| {
+            Processor::processor_connect_tcp(addr, None)
+        })
+        .await
+    }
+
+    ///
+    /// Connects to the given addresses, giving up on each address after the timeout.
+    ///
+    /// Connection attempts that don't complete in time fail with [io::ErrorKind::TimedOut].
+    pub async fn connect_timeout<A: ToSocketAddrs>(
+        sock_addrs: A,
+        timeout: Duration,
+    ) -> io::Result<Handle<TcpStream>> {
+        Processor::processor_connect(sock_addrs, |addr| {
+            Processor::processor_connect_tcp(addr, Some(timeout))
+        })
+        .await
+    }
+
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_send(
+                self.get_ref(),
+                buf,
+                self.write_timeout,
+            ))
+            .await
+    }
+
+    ///
+    /// Zero-copy send
+    ///
+    /// The kernel sends the data straight from the buffer instead of copying it, which pays off
+    /// for large buffers. The buffer is owned by the send until the kernel notifies that it is done
+    /// with it, and it is returned along with the number of bytes sent. Where zero-copy send isn't
+    /// supported, see [Proactor::capabilities], the data is copied as usual.
+    pub async fn send_zc<B: AsRef<[u8]> + Send + 'static>(&self, buf: B) -> io::Result<(usize, B)> {
+        self.proactor
+            .route(Processor::processor_send_zc(
+                self.get_ref(),
+                buf,
+                self.write_timeout,
+            ))
+            .await
+    }
+
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_recv(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
+    }
+
+    ///
+    /// Multishot receive
+    ///
+    /// Received chunks are buffers of the provided buffer ring of the proactor, which go back to
+    /// the ring when they are dropped. Where multishot receive or provided buffer rings aren't
+    /// supported, see [Proactor::capabilities], chunks are received one at a time instead.
+    pub async fn recv_multi(&self) -> io::Result<RecvStream> {
+        let _guard = self.proactor.enter();
+        RecvStream::new(self.get_ref())
+    }
+
+    pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_peek(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
+    }
+}
+
+impl Handle<UdpSocket> {
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Handle<UdpSocket>> {
+        Handle::new(UdpSocket::bind(addr)?)
+    }
+
+    pub async fn connect<A: ToSocketAddrs>(sock_addrs: A) -> io::Result<Handle<UdpSocket>> {
+        Processor::processor_connect(sock_addrs, Processor::processor_connect_udp).await
+    }
+
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_send(
+                self.get_ref(),
+                buf,
+                self.write_timeout,
+            ))
+            .await
+    }
+
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_recv(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
+    }
+
+    pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_peek(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
+    }
+
+    pub async fn send_to<A: ToSocketAddrs>(&self, buf: &[u8], addr: A) -> io::Result<usize> {
+        match addr.to_socket_addrs()?.next() {
+            Some(addr) => {
+                self.proactor
+                    .route(Processor::processor_send_to(
+                        self.get_ref(),
+                        buf,
+                        addr,
+                        self.write_timeout,
+                    ))
+                    .await
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "given addresses can't be parsed",
+            )),
+        }
+    }
+
+    ///
+    /// Zero-copy send to the address, see [Handle::send_zc](Handle<TcpStream>::send_zc).
+    pub async fn send_to_zc<B: AsRef<[u8]> + Send + 'static, A: ToSocketAddrs>(
+        &self,
+        buf: B,
+        addr: A,
+    ) -> io::Result<(usize, B)> {
+        match addr.to_socket_addrs()?.next() {
+            Some(addr) => {
+                self.proactor
+                    .route(Processor::processor_send_to_zc(
+                        self.get_ref(),
+                        buf,
+                        addr,
+                        self.write_timeout,
+                    ))
+                    .await
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "given addresses can't be parsed",
+            )),
+        }
+    }
+
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.proactor
+            .route(Processor::processor_recv_from(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
+    }
+
+    pub async fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.proactor
+            .route(Processor::processor_peek_from(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
+    }
+}
+
+impl Handle<UnixListener> {
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<Handle<UnixListener>> {
+        Handle::new(UnixListener::bind(path)?)
+    }
+
+    pub async fn accept(&self) -> io::Result<(Handle<UnixStream>, UnixSocketAddr)> {
+        self.proactor
+            .route(Processor::processor_accept_unix_listener(
+                self.get_ref(),
+                self.read_timeout,
+            ))
+            .await
+    }
+
+    pub fn incoming(&self) -> impl Stream<Item = io::Result<Handle<UnixStream>>> + Unpin + '_ {
+        Box::pin(futures::stream::unfold(
+            self,
+            |listener: &Handle<UnixListener>| async move {
+                let res = listener.accept().await.map(|(stream, _)| stream);
+                Some((res, listener))
+            },
+        ))
+    }
+}
+
+impl Handle<UnixStream> {
+    pub async fn connect<P: AsRef<Path>>(path: P) -> io::Result<Handle<UnixStream>> {
+        Processor::processor_connect_unix(path).await
+    }
+
+    pub fn pair() -> io::Result<(Handle<UnixStream>, Handle<UnixStream>)> {
+        let (bidi_l, bidi_r) =
+            socket2::Socket::pair(socket2::Domain::unix(), socket2::Type::stream(), None)?;
+
+        Ok((
+            Handle::new(bidi_l.into_unix_stream())?,
+            Handle::new(bidi_r.into_unix_stream())?,
+        ))
+    }
+
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_send(
+                self.get_ref(),
+                buf,
+                self.write_timeout,
+            ))
+            .await
+    }
+
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_recv(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
+    }
+
+    ///
+    /// Multishot receive
+    ///
+    /// Received chunks are buffers of the provided buffer ring of the proactor, which go back to
+    /// the ring when they are dropped. Where multishot receive or provided buffer rings aren't
+    /// supported, see [Proactor::capabilities], chunks are received one at a time instead.
+    pub async fn recv_multi(&self) -> io::Result<RecvStream> {
+        let _guard = self.proactor.enter();
+        RecvStream::new(self.get_ref())
+    }
+
+    pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_peek(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
+    }
+}
+
+impl Handle<UnixDatagram> {
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<Handle<UnixDatagram>> {
+        Handle::new(UnixDatagram::bind(path)?)
+    }
+
+    pub fn pair() -> io::Result<(Handle<UnixDatagram>, Handle<UnixDatagram>)> {
+        let (bidi_l, bidi_r) =
+            socket2::Socket::pair(socket2::Domain::unix(), socket2::Type::dgram(), None)?;
+
+        Ok((
+            Handle::new(bidi_l.into_unix_datagram())?,
+            Handle::new(bidi_r.into_unix_datagram())?,
+        ))
+    }
+
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_send(
+                self.get_ref(),
+                buf,
+                self.write_timeout,
+            ))
+            .await
+    }
+
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_recv(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
+    }
+
+    pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_peek(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
+    }
+
+    pub async fn send_to<P: AsRef<Path>>(&self, buf: &[u8], path: P) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_send_to_unix(
+                self.get_ref(),
+                buf,
+                path,
+                self.write_timeout,
+            ))
+            .await
+    }
+
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, UnixSocketAddr)> {
+        self.proactor
+            .route(Processor::processor_recv_from_unix(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
+    }
+
+    pub async fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, UnixSocketAddr)> {
+        self.proactor
+            .route(Processor::processor_peek_from_unix(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
+    }
+}
+
+impl Handle<Direct> {
+    pub(crate) fn direct(io: Direct) -> Handle<Direct> {
+        let proactor = io.proactor.clone();
+        Handle::with_proactor(io, None, proactor)
+    }
+
+    ///
+    /// Opens the file for reading and writing, straight into the file table of the ring
+    ///
+    /// The file is a [Direct] descriptor, it is never installed to the descriptor table of the
+    /// process. Fails with [io::ErrorKind::Unsupported] on epoll and on kernels without allocated
+    /// slots, see [Proactor::capabilities].
+    pub async fn open_direct(path: impl AsRef<Path>) -> io::Result<Handle<Direct>> {
+        let slot = Processor::processor_open_direct(path).await?;
+
+        Ok(Handle::direct(Direct {
+            slot,
+            proactor: Proactor::current(),
+        }))
+    }
+
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_send_direct(
+                self.slot(),
+                buf,
+                self.write_timeout,
+            ))
+            .await
+    }
+
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_recv_direct(
+                self.slot(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
+    }
+
+    ///
+    /// Reads from the offset of the file, the descriptor has no position of its own.
+    pub async fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_read_direct(
+                self.slot(),
+                buf,
+                offset as _,
+                self.read_timeout,
+            ))
+            .await
+    }
+
+    ///
+    /// Writes to the offset of the file, the descriptor has no position of its own.
+    pub async fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.proactor
+            .route(Processor::processor_write_direct(
+                self.slot(),
+                buf,
+                offset as _,
+                self.write_timeout,
+            ))
+            .await
+    }
+}
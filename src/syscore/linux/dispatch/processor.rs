@@ -0,0 +1,416 @@
+use std::future::Future;
+use std::io;
+use std::io::{IoSlice, IoSliceMut};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{SocketAddr as UnixSocketAddr, UnixStream};
+use std::os::unix::prelude::RawFd;
+use std::path::Path;
+use std::time::Duration;
+
+use rustix_uring::types::Statx;
+
+use super::{epoll, iouring, SysProactor};
+use crate::proactor::Proactor;
+use crate::{FixedBuf, Handle};
+
+/// Dispatches the operation to the processor of the backend that is selected at runtime.
+macro_rules! dispatch {
+    ($op:ident($($arg:expr),* $(,)?)) => {
+        match Proactor::current().inner() {
+            SysProactor::IoUring(_) => iouring::Processor::$op($($arg),*).await,
+            SysProactor::Epoll(_) => epoll::Processor::$op($($arg),*).await,
+        }
+    };
+    ($uring_op:ident, $epoll_op:ident($($arg:expr),* $(,)?)) => {
+        match Proactor::current().inner() {
+            SysProactor::IoUring(_) => iouring::Processor::$uring_op($($arg),*).await,
+            SysProactor::Epoll(_) => epoll::Processor::$epoll_op($($arg),*).await,
+        }
+    };
+}
+
+/// Dispatches the operation on direct descriptors, which only exist on io_uring.
+macro_rules! direct {
+    ($op:ident($($arg:expr),* $(,)?)) => {
+        match Proactor::current().inner() {
+            SysProactor::IoUring(_) => iouring::Processor::$op($($arg),*).await,
+            SysProactor::Epoll(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "nuclei: direct descriptors require the io_uring backend",
+            )),
+        }
+    };
+}
+
+pub struct Processor;
+
+impl Processor {
+    ///////////////////////////////////
+    ///// Read Write
+    ///////////////////////////////////
+
+    pub(crate) async fn processor_open_at(path: impl AsRef<Path>) -> io::Result<usize> {
+        dispatch!(processor_open_at(path))
+    }
+
+    pub(crate) async fn processor_read_file(
+        io: &RawFd,
+        buf: &mut [u8],
+        offset: usize,
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        match Proactor::current().inner() {
+            SysProactor::IoUring(_) => {
+                iouring::Processor::processor_read_file(io, buf, offset, timeout).await
+            }
+            // Regular files are always readable, epoll reads them synchronously.
+            SysProactor::Epoll(_) => {
+                epoll::Processor::processor_read_file_at(io, buf, offset).await
+            }
+        }
+    }
+
+    pub(crate) async fn processor_write_file(
+        io: &RawFd,
+        buf: &[u8],
+        offset: usize,
+    ) -> io::Result<usize> {
+        dispatch!(
+            processor_write_file,
+            processor_write_file_at(io, buf, offset)
+        )
+    }
+
+    pub(crate) async fn processor_read_fixed(
+        io: &RawFd,
+        buf: FixedBuf,
+        offset: usize,
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, FixedBuf)> {
+        dispatch!(processor_read_fixed(io, buf, offset, timeout))
+    }
+
+    pub(crate) async fn processor_write_fixed(
+        io: &RawFd,
+        buf: FixedBuf,
+        offset: usize,
+    ) -> io::Result<(usize, FixedBuf)> {
+        dispatch!(processor_write_fixed(io, buf, offset))
+    }
+
+    pub(crate) async fn processor_close_file(io: &RawFd) -> io::Result<usize> {
+        dispatch!(processor_close_file(io))
+    }
+
+    pub(crate) async fn processor_register_direct(io: &RawFd) -> io::Result<u32> {
+        direct!(processor_register_direct(io))
+    }
+
+    pub(crate) async fn processor_open_direct(path: impl AsRef<Path>) -> io::Result<u32> {
+        direct!(processor_open_direct(path))
+    }
+
+    pub(crate) async fn processor_read_direct(
+        slot: u32,
+        buf: &mut [u8],
+        offset: usize,
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        direct!(processor_read_direct(slot, buf, offset, timeout))
+    }
+
+    pub(crate) async fn processor_write_direct(
+        slot: u32,
+        buf: &[u8],
+        offset: usize,
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        direct!(processor_write_direct(slot, buf, offset, timeout))
+    }
+
+    pub(crate) async fn processor_file_size(io: &RawFd, statx: *mut Statx) -> io::Result<usize> {
+        match Proactor::current().inner() {
+            SysProactor::IoUring(_) => iouring::Processor::processor_file_size(io, statx).await,
+            SysProactor::Epoll(_) => epoll::Processor::processor_file_size(io).await,
+        }
+    }
+
+    pub(crate) async fn processor_read_vectored(
+        io: &RawFd,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> io::Result<usize> {
+        dispatch!(
+            processor_read_vectored,
+            processor_read_vectored_at(io, bufs)
+        )
+    }
+
+    pub(crate) async fn processor_write_vectored(
+        io: &RawFd,
+        bufs: &[IoSlice<'_>],
+    ) -> io::Result<usize> {
+        dispatch!(
+            processor_write_vectored,
+            processor_write_vectored_at(io, bufs)
+        )
+    }
+
+    ///////////////////////////////////
+    ///// Splice, Tee
+    ///////////////////////////////////
+
+    pub(crate) async fn processor_splice(
+        fd_in: &RawFd,
+        off_in: Option<u64>,
+        fd_out: &RawFd,
+        len: usize,
+    ) -> io::Result<usize> {
+        dispatch!(processor_splice(fd_in, off_in, fd_out, len))
+    }
+
+    pub(crate) async fn processor_tee(
+        fd_in: &RawFd,
+        fd_out: &RawFd,
+        len: usize,
+    ) -> io::Result<usize> {
+        dispatch!(processor_tee(fd_in, fd_out, len))
+    }
+
+    ///////////////////////////////////
+    ///// Send, Recv, Peek
+    ///// Commonality of TcpStream, UdpSocket, UnixStream, UnixDatagram
+    ///////////////////////////////////
+
+    pub(crate) async fn processor_send<R: AsRawFd>(
+        socket: &R,
+        buf: &[u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        dispatch!(processor_send(socket, buf, timeout))
+    }
+
+    pub(crate) async fn processor_send_zc<R: AsRawFd, B: AsRef<[u8]> + Send + 'static>(
+        socket: &R,
+        buf: B,
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, B)> {
+        match Proactor::current().inner() {
+            SysProactor::IoUring(_) => {
+                iouring::Processor::processor_send_zc(socket, buf, timeout).await
+            }
+            // epoll sends copy the data, the buffer is released as soon as the send returns.
+            SysProactor::Epoll(_) => {
+                let n = epoll::Processor::processor_send(socket, buf.as_ref(), timeout).await?;
+                Ok((n, buf))
+            }
+        }
+    }
+
+    pub(crate) async fn processor_recv<R: AsRawFd>(
+        sock: &R,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        dispatch!(processor_recv(sock, buf, timeout))
+    }
+
+    pub(crate) async fn processor_peek<R: AsRawFd>(
+        sock: &R,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        dispatch!(processor_peek(sock, buf, timeout))
+    }
+
+    pub(crate) async fn processor_send_fixed<R: AsRawFd>(
+        socket: &R,
+        buf: FixedBuf,
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, FixedBuf)> {
+        dispatch!(processor_send_fixed(socket, buf, timeout))
+    }
+
+    pub(crate) async fn processor_recv_fixed<R: AsRawFd>(
+        socket: &R,
+        buf: FixedBuf,
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, FixedBuf)> {
+        dispatch!(processor_recv_fixed(socket, buf, timeout))
+    }
+
+    pub(crate) async fn processor_send_direct(
+        slot: u32,
+        buf: &[u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        direct!(processor_send_direct(slot, buf, timeout))
+    }
+
+    pub(crate) async fn processor_recv_direct(
+        slot: u32,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        direct!(processor_recv_direct(slot, buf, timeout))
+    }
+
+    ///////////////////////////////////
+    ///// Connect
+    ///// Commonality of TcpStream, UdpSocket
+    ///////////////////////////////////
+
+    pub(crate) async fn processor_connect<A: ToSocketAddrs, F, Fut, T>(
+        addrs: A,
+        mut f: F,
+    ) -> io::Result<T>
+    where
+        F: FnMut(SocketAddr) -> Fut,
+        Fut: Future<Output = io::Result<T>>,
+    {
+        let addrs = addrs.to_socket_addrs()?;
+
+        let mut tail_err = None;
+        for addr in addrs {
+            match f(addr).await {
+                Ok(l) => return Ok(l),
+                Err(e) => tail_err = Some(e),
+            }
+        }
+
+        Err(tail_err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "couldn't resolve addresses")
+        }))
+    }
+
+    pub(crate) async fn processor_connect_tcp(
+        addr: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> io::Result<Handle<TcpStream>> {
+        dispatch!(processor_connect_tcp(addr, timeout))
+    }
+
+    pub(crate) async fn processor_connect_udp(addr: SocketAddr) -> io::Result<Handle<UdpSocket>> {
+        dispatch!(processor_connect_udp(addr))
+    }
+
+    ///////////////////////////////////
+    ///// TcpListener
+    ///////////////////////////////////
+
+    pub(crate) async fn processor_accept_tcp_listener<R: AsRawFd>(
+        listener: &R,
+        timeout: Option<Duration>,
+    ) -> io::Result<(Handle<TcpStream>, Option<SocketAddr>)> {
+        match Proactor::current().inner() {
+            SysProactor::IoUring(_) => {
+                iouring::Processor::processor_accept_tcp_listener(listener, timeout).await
+            }
+            SysProactor::Epoll(_) => {
+                epoll::Processor::processor_accept_tcp_listener(listener, timeout)
+                    .await
+                    .map(|(stream, addr)| (stream, Some(addr)))
+            }
+        }
+    }
+
+    pub(crate) async fn processor_accept_direct<R: AsRawFd>(
+        listener: &R,
+        timeout: Option<Duration>,
+    ) -> io::Result<u32> {
+        direct!(processor_accept_direct(listener, timeout))
+    }
+
+    ///////////////////////////////////
+    ///// UdpSocket
+    ///////////////////////////////////
+
+    pub(crate) async fn processor_send_to<R: AsRawFd>(
+        socket: &R,
+        buf: &[u8],
+        addr: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        dispatch!(processor_send_to(socket, buf, addr, timeout))
+    }
+
+    pub(crate) async fn processor_send_to_zc<R: AsRawFd, B: AsRef<[u8]> + Send + 'static>(
+        socket: &R,
+        buf: B,
+        addr: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, B)> {
+        match Proactor::current().inner() {
+            SysProactor::IoUring(_) => {
+                iouring::Processor::processor_send_to_zc(socket, buf, addr, timeout).await
+            }
+            SysProactor::Epoll(_) => {
+                let n = epoll::Processor::processor_send_to(socket, buf.as_ref(), addr, timeout)
+                    .await?;
+                Ok((n, buf))
+            }
+        }
+    }
+
+    pub(crate) async fn processor_recv_from<R: AsRawFd>(
+        sock: &R,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, SocketAddr)> {
+        dispatch!(processor_recv_from(sock, buf, timeout))
+    }
+
+    pub(crate) async fn processor_peek_from<R: AsRawFd>(
+        sock: &R,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, SocketAddr)> {
+        dispatch!(processor_peek_from(sock, buf, timeout))
+    }
+
+    ///////////////////////////////////
+    ///// UnixListener
+    ///////////////////////////////////
+
+    pub(crate) async fn processor_accept_unix_listener<R: AsRawFd>(
+        listener: &R,
+        timeout: Option<Duration>,
+    ) -> io::Result<(Handle<UnixStream>, UnixSocketAddr)> {
+        dispatch!(processor_accept_unix_listener(listener, timeout))
+    }
+
+    ///////////////////////////////////
+    ///// UnixStream
+    ///////////////////////////////////
+
+    pub(crate) async fn processor_connect_unix<P: AsRef<Path>>(
+        path: P,
+    ) -> io::Result<Handle<UnixStream>> {
+        dispatch!(processor_connect_unix(path))
+    }
+
+    pub(crate) async fn processor_send_to_unix<R: AsRawFd, P: AsRef<Path>>(
+        socket: &R,
+        buf: &[u8],
+        path: P,
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        dispatch!(processor_send_to_unix(socket, buf, path, timeout))
+    }
+
+    pub(crate) async fn processor_recv_from_unix<R: AsRawFd>(
+        socket: &R,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, UnixSocketAddr)> {
+        dispatch!(processor_recv_from_unix(socket, buf, timeout))
+    }
+
+    pub(crate) async fn processor_peek_from_unix<R: AsRawFd>(
+        socket: &R,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, UnixSocketAddr)> {
+        dispatch!(processor_peek_from_unix(socket, buf, timeout))
+    }
+}
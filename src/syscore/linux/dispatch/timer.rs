@@ -0,0 +1,29 @@
+use std::io;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use super::{epoll, iouring, SysProactor};
+use crate::proactor::Proactor;
+
+///
+/// Timer of the backend that is selected at runtime.
+pub(crate) enum Timer {
+    IoUring(iouring::Timer),
+    Epoll(epoll::Timer),
+}
+
+impl Timer {
+    pub(crate) fn new(duration: Duration, periodic: bool) -> io::Result<Timer> {
+        match Proactor::current().inner() {
+            SysProactor::IoUring(_) => iouring::Timer::new(duration, periodic).map(Timer::IoUring),
+            SysProactor::Epoll(_) => epoll::Timer::new(duration, periodic).map(Timer::Epoll),
+        }
+    }
+
+    pub(crate) fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self {
+            Timer::IoUring(t) => t.poll_tick(cx),
+            Timer::Epoll(t) => t.poll_tick(cx),
+        }
+    }
+}
@@ -1,14 +1,20 @@
 use crate::sys::epoll::*;
+use crate::sys::IoBackend;
 use ahash::{HashMap, HashMapExt};
 use futures::channel::oneshot;
 use lever::prelude::*;
 use pin_utils::unsafe_pinned;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::future::Future;
 use std::io::{self, Read, Write};
 use std::mem::MaybeUninit;
 use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Instant;
 use std::{fs::File, time::Duration};
 
 macro_rules! syscall {
@@ -25,7 +31,48 @@ macro_rules! syscall {
 ///////////////////
 ///////////////////
 
-use crate::config::NucleiConfig;
+/// `struct epoll_params` of `EPIOCSPARAMS`.
+#[repr(C)]
+struct EpollParams {
+    busy_poll_usecs: u32,
+    busy_poll_budget: u16,
+    prefer_busy_poll: u8,
+    pad: u8,
+}
+
+/// `_IOW(0x8A, 0x01, struct epoll_params)`
+const EPIOCSPARAMS: libc::c_ulong = 0x4008_8a01;
+
+/// Sets the busy polling parameters of the epoll instance.
+fn set_busy_poll(epoll_fd: RawFd, busy_poll: EpollBusyPoll) -> io::Result<()> {
+    let params = EpollParams {
+        busy_poll_usecs: busy_poll.usecs,
+        busy_poll_budget: busy_poll.budget,
+        prefer_busy_poll: busy_poll.prefer as u8,
+        pad: 0,
+    };
+
+    match syscall!(ioctl(
+        epoll_fd,
+        EPIOCSPARAMS as _,
+        &params as *const EpollParams
+    )) {
+        Err(e) if e.raw_os_error() == Some(libc::ENOTTY) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "nuclei: epoll busy polling requires Linux 6.9+",
+        )),
+        res => res.map(drop),
+    }
+}
+
+use crate::config::{EpollBusyPoll, EpollTrigger, NucleiConfig};
+use crate::driver::Driver;
+use crate::latency::{Latencies, LatencyHistogram, OpClass};
+use crate::proactor::{
+    Capabilities, Current, InflightOperation, Proactor, ProactorStats, ShutdownMode,
+};
+use crate::syscore::ShutdownState;
+use crate::{EventHook, OpEvent, OpEventKind};
 use socket2::SockAddr;
 use std::mem;
 use std::os::unix::net::SocketAddr as UnixSocketAddr;
@@ -132,7 +179,71 @@ pub(crate) fn shim_to_af_unix(sockaddr: &SockAddr) -> io::Result<UnixSocketAddr>
 //// Socket Addr
 ///////////////////
 
-type CompletionList = Vec<(i32, oneshot::Sender<i32>)>;
+/// Expiry of an interest, ids break the ties between the same instants.
+type Deadline = (Instant, u64);
+
+///
+/// Interest of an operation in the events of an fd.
+struct Interest {
+    events: i32,
+    tx: oneshot::Sender<i32>,
+    /// Id of the interest, it also breaks the ties between the same deadlines.
+    id: u64,
+    deadline: Option<Instant>,
+    /// Class of the operation, [None] for the internal interests like timers.
+    class: Option<OpClass>,
+    registered_at: Instant,
+}
+
+impl Interest {
+    fn event(&self, kind: OpEventKind, fd: RawFd, result: i32) -> OpEvent {
+        OpEvent {
+            kind,
+            opcode: None,
+            class: self.class,
+            fd,
+            user_data: self.id,
+            result,
+            bytes: 0,
+        }
+    }
+}
+
+type CompletionList = Vec<Interest>;
+
+/// Epoll proactor of the current Nuclei instance.
+pub(crate) fn sys_proactor() -> Current<SysProactor> {
+    Current::new(epoll_of)
+}
+
+/// Epoll proactor of the given Nuclei instance.
+pub(crate) fn epoll_of(proactor: &crate::syscore::SysProactor) -> &SysProactor {
+    #[cfg(feature = "iouring")]
+    {
+        proactor.epoll()
+    }
+    #[cfg(not(feature = "iouring"))]
+    {
+        proactor
+    }
+}
+
+/// Flags that the file descriptors are registered with, along with the events of their interests.
+fn registration_flags(trigger: EpollTrigger, exclusive: bool) -> EpollFlags {
+    // Don't use RDHUP as registered with EPOLLIN.
+    // Not all data might read with that.
+    let mut flags = libc::EPOLLHUP;
+    if trigger == EpollTrigger::Edge {
+        flags |= libc::EPOLLET;
+    }
+    if exclusive {
+        // Only the in/out events are allowed along with EPOLLEXCLUSIVE.
+        flags |= libc::EPOLLEXCLUSIVE;
+    } else {
+        flags |= libc::EPOLLPRI;
+    }
+    flags
+}
 
 pub struct SysProactor {
     /// epoll_fd
@@ -146,6 +257,41 @@ pub struct SysProactor {
 
     /// Hashmap for holding interested concrete completion callbacks
     completions: TTas<HashMap<RawFd, CompletionList>>,
+
+    /// Deadlines of the interests that are registered with a timeout
+    timers: TTas<BTreeMap<Deadline, RawFd>>,
+
+    /// Interest id generator
+    interest_id: AtomicU64,
+
+    state: ShutdownState,
+
+    /// Slot of the thread that drives the proactor
+    driver: Driver,
+
+    /// Interests registered
+    submitted: AtomicU64,
+
+    /// Events dispatched to interests
+    reaped: AtomicU64,
+
+    /// Wakeups through the event fd
+    wakeups: AtomicU64,
+
+    /// Latencies from registering interests to dispatching their events
+    latencies: Latencies,
+
+    /// Hook that is called with the events of the interests
+    hook: Option<Arc<dyn EventHook>>,
+
+    /// Events read per wait, overrides the amount that is asked for
+    max_events: Option<usize>,
+
+    /// Flags that the file descriptors are registered with along with their interests
+    flags: EpollFlags,
+
+    /// Whether the file descriptors are registered with `EPOLLEXCLUSIVE`
+    exclusive: bool,
 }
 
 impl SysProactor {
@@ -159,6 +305,18 @@ impl SysProactor {
             event_fd: TTas::new(event_fd),
             registered: TTas::new(HashMap::new()),
             completions: TTas::new(HashMap::new()),
+            timers: TTas::new(BTreeMap::new()),
+            interest_id: AtomicU64::new(0),
+            state: ShutdownState::new(),
+            driver: Driver::new(),
+            submitted: AtomicU64::new(0),
+            reaped: AtomicU64::new(0),
+            wakeups: AtomicU64::new(0),
+            latencies: Latencies::new(),
+            hook: config.event_hook.clone(),
+            max_events: config.epoll.max_events,
+            flags: registration_flags(config.epoll.trigger, config.epoll.exclusive),
+            exclusive: config.epoll.exclusive,
         };
 
         let ev = &mut EpollEvent::new(libc::EPOLLIN as _, 0 as u64);
@@ -169,24 +327,27 @@ impl SysProactor {
             Some(ev),
         )?;
 
-        Ok(proactor)
-    }
+        if let Some(busy_poll) = config.epoll.busy_poll {
+            set_busy_poll(proactor.epoll_fd, busy_poll)?;
+        }
 
-    fn default_flags(&self) -> EpollFlags {
-        // Don't use RDHUP as registered with EPOLLIN.
-        // Not all data might read with that.
-        libc::EPOLLHUP | libc::EPOLLPRI | libc::EPOLLET
+        Ok(proactor)
     }
 
     pub fn register(&self, fd: RawFd, events: i32) -> io::Result<()> {
         let flags = syscall!(fcntl(fd, libc::F_GETFL))?;
         syscall!(fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK))?;
-        let ev = &mut EpollEvent::new(events | self.default_flags(), fd as u64);
+        let ev = &mut EpollEvent::new(events | self.flags, fd as u64);
         epoll_ctl(self.epoll_fd, EpollOp::EpollCtlAdd, fd, Some(ev))
     }
 
     pub fn reregister(&self, fd: RawFd, events: i32) -> io::Result<()> {
-        let ev = &mut EpollEvent::new(events | libc::EPOLLET, !0 as u64);
+        let ev = &mut EpollEvent::new(events | self.flags, fd as u64);
+        if self.exclusive {
+            // Exclusive registrations can't be modified.
+            self.deregister(fd)?;
+            return epoll_ctl(self.epoll_fd, EpollOp::EpollCtlAdd, fd, Some(ev));
+        }
         epoll_ctl(self.epoll_fd, EpollOp::EpollCtlMod, fd, Some(ev))
     }
 
@@ -194,31 +355,63 @@ impl SysProactor {
         epoll_ctl(self.epoll_fd, EpollOp::EpollCtlDel, fd, None)
     }
 
+    pub(crate) fn wait_parked(&self, max_event_size: usize) -> io::Result<usize> {
+        self.wait(max_event_size, None)
+    }
+
     pub fn wait(&self, max_event_size: usize, timeout: Option<Duration>) -> io::Result<usize> {
+        if self.state.is_stopped() {
+            return Ok(0);
+        }
+
+        let max_event_size = self.max_events.unwrap_or(max_event_size).max(1);
         let mut events: Vec<EpollEvent> = Vec::with_capacity(max_event_size);
         events.resize(max_event_size, unsafe {
             MaybeUninit::zeroed().assume_init()
         });
 
-        let timeout: isize = timeout.map_or(!0, |d| d.as_millis() as isize);
+        // Don't sleep past the nearest deadline, it needs to be expired in time.
+        let nearest = self
+            .timers
+            .lock()
+            .keys()
+            .next()
+            .map(|(deadline, _)| deadline.saturating_duration_since(Instant::now()));
+        let timeout = match (timeout, nearest) {
+            (Some(t), Some(n)) => Some(t.min(n)),
+            (t, n) => t.or(n),
+        };
+
+        // Round up to the millisecond, otherwise the deadline is busy polled.
+        let timeout: isize = timeout.map_or(!0, |d| {
+            (d.as_millis() + (d.subsec_nanos() % 1_000_000 != 0) as u128) as isize
+        });
         let mut res = epoll_wait(self.epoll_fd, &mut events, timeout)? as usize;
 
-        let mut ev_fd = self.event_fd.lock();
         for event in &events[0..res] {
             if event.data() == 0 {
                 let mut buf = vec![0; 8];
-                let _ = ev_fd.read(&mut buf);
+                let _ = self.event_fd.lock().read(&mut buf);
                 res -= 1;
                 continue;
             }
 
+            // Completions wake their tasks, which in turn wake the proactor through the event fd.
+            // So event fd shouldn't be locked while dequeueing.
             self.dequeue_events(event.data() as _, event.events());
         }
 
+        self.expire_timers();
+
         Ok(res)
     }
 
+    pub(crate) fn backend(&self) -> IoBackend {
+        IoBackend::Epoll
+    }
+
     pub(crate) fn wake(&self) -> io::Result<()> {
+        self.wakeups.fetch_add(1, Ordering::Relaxed);
         self.event_fd.lock().write_all(&(1 as u64).to_ne_bytes())?;
         Ok(())
     }
@@ -226,6 +419,34 @@ impl SysProactor {
     ///////
 
     pub(crate) fn register_io(&self, fd: RawFd, events: i32) -> io::Result<CompletionChan> {
+        self.register_interest(fd, events, None, None)
+    }
+
+    /// Registers interest for the events of the fd on behalf of an operation of the class,
+    /// time until the events arrive is recorded as its latency.
+    pub(crate) fn register_op(
+        &self,
+        class: OpClass,
+        fd: RawFd,
+        events: i32,
+        timeout: Option<Duration>,
+    ) -> io::Result<CompletionChan> {
+        self.register_interest(fd, events, timeout, Some(class))
+    }
+
+    /// Registers interest for the events of the fd.
+    ///
+    /// If the events don't arrive within the timeout, interest is dropped and the completion
+    /// resolves with [io::ErrorKind::TimedOut].
+    fn register_interest(
+        &self,
+        fd: RawFd,
+        events: i32,
+        timeout: Option<Duration>,
+        class: Option<OpClass>,
+    ) -> io::Result<CompletionChan> {
+        self.state.check()?;
+
         let mut events = events;
         let mut registered = self.registered.lock();
         let mut completions = self.completions.lock();
@@ -242,9 +463,215 @@ impl SysProactor {
         let (tx, rx) = oneshot::channel();
         let comp = completions.entry(fd).or_insert(Vec::new());
 
-        comp.push((events, tx));
+        let interest = Interest {
+            events,
+            tx,
+            id: self.interest_id.fetch_add(1, Ordering::Relaxed),
+            deadline: timeout.map(|t| Instant::now() + t),
+            class,
+            registered_at: Instant::now(),
+        };
+        let submit = interest.event(OpEventKind::Submit, fd, 0);
+        let id = interest.id;
+
+        let mut earliest = false;
+        if let Some(deadline) = interest.deadline {
+            let mut timers = self.timers.lock();
+            timers.insert((deadline, interest.id), fd);
+            earliest = timers.keys().next() == Some(&(deadline, interest.id));
+        }
+
+        comp.push(interest);
+        self.submitted.fetch_add(1, Ordering::Relaxed);
+
+        drop(completions);
+        drop(registered);
+        self.emit(submit);
+
+        // Waiting thread might be sleeping past the new deadline.
+        if earliest {
+            self.wake()?;
+        }
+
+        Ok(CompletionChan {
+            rx,
+            fd,
+            id,
+            proactor: Proactor::current(),
+        })
+    }
+
+    /// Drops the interest of a completion that is dropped before its events arrive.
+    fn cancel_interest(&self, fd: RawFd, id: u64) {
+        let mut registered = self.registered.lock();
+        let mut completions = self.completions.lock();
+
+        let Some(comp) = completions.get_mut(&fd) else {
+            return;
+        };
+        let Some(i) = comp.iter().position(|interest| interest.id == id) else {
+            return;
+        };
+        let interest = comp.remove(i);
+        if let Some(deadline) = interest.deadline {
+            self.timers.lock().remove(&(deadline, id));
+        }
+        if comp.is_empty() {
+            completions.remove(&fd);
+            if registered.remove(&fd).is_some() {
+                let _ = self.deregister(fd);
+            }
+        }
+
+        drop(completions);
+        drop(registered);
+        self.emit(interest.event(OpEventKind::Cancel, fd, 0));
+    }
+
+    /// Drops all interests of the fd, before the fd is closed.
+    pub(crate) fn deregister_io(&self, fd: RawFd) {
+        let mut registered = self.registered.lock();
+        let mut completions = self.completions.lock();
+
+        let comp = completions.remove(&fd).unwrap_or_default();
+        let mut timers = self.timers.lock();
+        for interest in comp.iter() {
+            if let Some(deadline) = interest.deadline {
+                timers.remove(&(deadline, interest.id));
+            }
+        }
+        drop(timers);
+
+        if registered.remove(&fd).is_some() {
+            let _ = self.deregister(fd);
+        }
+
+        drop(completions);
+        drop(registered);
+        for interest in comp {
+            self.emit(interest.event(OpEventKind::Cancel, fd, 0));
+        }
+    }
+
+    /// Calls the event hook, if there is one, without any of the locks held.
+    fn emit(&self, event: OpEvent) {
+        if let Some(hook) = &self.hook {
+            hook.on_event(&event);
+        }
+    }
+
+    /// Stops accepting interests, and waits until the registered ones complete or get cancelled.
+    ///
+    /// Threads that wait for events are released once nothing is registered.
+    pub(crate) fn shutdown(&self, mode: ShutdownMode) -> io::Result<()> {
+        self.state.drain();
+
+        if let ShutdownMode::Drain(grace) = mode {
+            // Without a deadline, interests are drained until they complete.
+            let deadline = Instant::now().checked_add(grace);
+            while !self.completions.lock().is_empty() {
+                let remaining = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+                if remaining == Some(Duration::ZERO) {
+                    break;
+                }
+                // Another waiting thread might be dispatching the events, check back regularly.
+                let tick = Duration::from_millis(1);
+                self.wait(64, Some(remaining.map_or(tick, |r| r.min(tick))))?;
+            }
+        }
+
+        self.cancel_all();
+        self.state.stop();
+        self.wake()
+    }
+
+    pub(crate) fn is_shutdown(&self) -> bool {
+        self.state.is_stopped()
+    }
+
+    pub(crate) fn stats(&self) -> ProactorStats {
+        let registered = self.registered.lock().len() as u64;
+        let completions = self.completions.lock();
+
+        ProactorStats {
+            sqes_submitted: self.submitted.load(Ordering::Relaxed),
+            cqes_reaped: self.reaped.load(Ordering::Relaxed),
+            inflight: completions.values().map(|comp| comp.len() as u64).sum(),
+            wakeups: self.wakeups.load(Ordering::Relaxed),
+            registered_fds: registered,
+            completions: completions.len() as u64,
+            ..ProactorStats::default()
+        }
+    }
 
-        Ok(CompletionChan { rx })
+    pub(crate) fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    /// Buffers are read into and written from as they are, there is nothing to register.
+    pub(crate) fn register_buffers(&self, _bufs: &[libc::iovec]) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub(crate) fn unregister_buffers(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub(crate) fn driver(&self) -> &Driver {
+        &self.driver
+    }
+
+    pub(crate) fn inflight(&self) -> Vec<InflightOperation> {
+        let now = Instant::now();
+        let mut ops: Vec<InflightOperation> = self
+            .completions
+            .lock()
+            .iter()
+            .flat_map(|(fd, comp)| {
+                comp.iter().map(move |interest| InflightOperation {
+                    id: interest.id,
+                    opcode: None,
+                    class: interest.class,
+                    fd: *fd,
+                    events: interest.events as u32,
+                    cancelled: false,
+                    submitted_at: interest.registered_at,
+                    age: now.saturating_duration_since(interest.registered_at),
+                })
+            })
+            .collect();
+        ops.sort_by_key(|op| (op.submitted_at, op.id));
+        ops
+    }
+
+    pub(crate) fn latency(&self, class: OpClass) -> LatencyHistogram {
+        self.latencies.histogram(class)
+    }
+
+    /// Drops every interest, their completions resolve with `ECANCELED`.
+    fn cancel_all(&self) {
+        let mut registered = self.registered.lock();
+        let mut completions = self.completions.lock();
+        self.timers.lock().clear();
+
+        let mut events = Vec::new();
+        for (fd, comp) in completions.drain() {
+            for interest in comp {
+                if self.hook.is_some() {
+                    events.push(interest.event(OpEventKind::Cancel, fd, 0));
+                    events.push(interest.event(OpEventKind::Error, fd, -libc::ECANCELED));
+                }
+                let _ = interest.tx.send(-libc::ECANCELED);
+            }
+        }
+
+        for (fd, _) in registered.drain() {
+            let _ = self.deregister(fd);
+        }
+
+        drop(completions);
+        drop(registered);
+        events.into_iter().for_each(|event| self.emit(event));
     }
 
     fn dequeue_events(&self, fd: RawFd, evts: i32) {
@@ -263,13 +690,28 @@ impl SysProactor {
         }
 
         // send concrete completion and remove completion interested sources
+        // errors and hangups are delivered to all interests.
         let mut ack_removal = false;
+        let mut events = Vec::new();
         if let Some(completions) = completions.get_mut(&fd) {
             let mut i = 0;
             while i < completions.len() {
-                if completions[i].0 & evts != 0 {
-                    let (_evts, sender) = completions.remove(i);
-                    let _ = sender.send(evts);
+                if completions[i].events & evts != 0
+                    || evts & (libc::EPOLLERR | libc::EPOLLHUP) != 0
+                {
+                    let interest = completions.remove(i);
+                    if let Some(deadline) = interest.deadline {
+                        self.timers.lock().remove(&(deadline, interest.id));
+                    }
+                    if let Some(class) = interest.class {
+                        self.latencies
+                            .record(class, interest.registered_at.elapsed());
+                    }
+                    if self.hook.is_some() {
+                        events.push(interest.event(OpEventKind::Complete, fd, evts));
+                    }
+                    let _ = interest.tx.send(evts);
+                    self.reaped.fetch_add(1, Ordering::Relaxed);
                 } else {
                     i += 1;
                 }
@@ -288,6 +730,62 @@ impl SysProactor {
         if ack_removal {
             completions.remove(&fd);
         }
+
+        drop(completions);
+        drop(registered);
+        events.into_iter().for_each(|event| self.emit(event));
+    }
+
+    /// Drops the interests whose deadline has passed, their completions resolve as timed out.
+    fn expire_timers(&self) {
+        let mut registered = self.registered.lock();
+        let mut completions = self.completions.lock();
+        let mut timers = self.timers.lock();
+
+        let now = Instant::now();
+        let mut events = Vec::new();
+        while let Some((&deadline, &fd)) = timers.iter().next() {
+            if deadline.0 > now {
+                break;
+            }
+            timers.remove(&deadline);
+
+            if let Some(comp) = completions.get_mut(&fd) {
+                if let Some(i) = comp.iter().position(|interest| interest.id == deadline.1) {
+                    let interest = comp.remove(i);
+                    if self.hook.is_some() {
+                        events.push(interest.event(OpEventKind::Error, fd, -libc::ETIMEDOUT));
+                    }
+                    let _ = interest.tx.send(-libc::ETIMEDOUT);
+                }
+                if comp.is_empty() {
+                    completions.remove(&fd);
+                    if registered.remove(&fd).is_some() {
+                        let _ = self.deregister(fd);
+                    }
+                }
+            }
+        }
+
+        drop(timers);
+        drop(completions);
+        drop(registered);
+        events.into_iter().for_each(|event| self.emit(event));
+    }
+}
+
+impl fmt::Debug for SysProactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SysProactor")
+            .field("backend", &self.backend())
+            .field("inflight", &self.inflight())
+            .finish()
+    }
+}
+
+impl Drop for SysProactor {
+    fn drop(&mut self) {
+        let _ = syscall!(close(self.epoll_fd));
     }
 }
 
@@ -296,6 +794,11 @@ impl SysProactor {
 
 pub(crate) struct CompletionChan {
     rx: oneshot::Receiver<i32>,
+    fd: RawFd,
+    /// Id of the interest, it is dropped along with the completion.
+    id: u64,
+    /// Proactor the interest is registered to.
+    proactor: Proactor,
 }
 
 impl CompletionChan {
@@ -306,8 +809,24 @@ impl Future for CompletionChan {
     type Output = io::Result<i32>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        self.rx()
-            .poll(cx)
-            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "sender has been cancelled"))
+        match futures::ready!(self.rx().poll(cx)) {
+            // Interest is dropped by a shutdown, or its timeout expired.
+            Ok(res) if res < 0 => Poll::Ready(Err(io::Error::from_raw_os_error(-res))),
+            Ok(res) => Poll::Ready(Ok(res)),
+            // Interest is dropped along with its fd, it isn't a timeout.
+            Err(_) => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "nuclei: operation is dropped before it completes",
+            ))),
+        }
+    }
+}
+
+impl Drop for CompletionChan {
+    fn drop(&mut self) {
+        // Interest is still registered until its events arrive.
+        if let Ok(None) = self.rx.try_recv() {
+            epoll_of(self.proactor.inner()).cancel_interest(self.fd, self.id);
+        }
     }
 }
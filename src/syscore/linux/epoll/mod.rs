@@ -1,11 +1,14 @@
 mod epoll;
+#[cfg(not(feature = "iouring"))]
 mod fs;
+#[cfg(not(feature = "iouring"))]
 mod nethandle;
 mod processor;
+mod timer;
 
 pub(crate) use epoll::*;
+#[cfg(not(feature = "iouring"))]
 pub(crate) use fs::*;
 
 pub(crate) use processor::*;
-
-pub const BACKEND: crate::sys::IoBackend = crate::sys::IoBackend::Epoll;
+pub(crate) use timer::*;
@@ -4,6 +4,7 @@ use std::net::{SocketAddr, ToSocketAddrs};
 use std::os::unix::io::AsRawFd;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use std::net::{TcpListener, TcpStream, UdpSocket};
 // Unix specifics
@@ -12,7 +13,7 @@ use std::os::unix::net::{SocketAddr as UnixSocketAddr, UnixDatagram, UnixListene
 use futures::Stream;
 use lever::sync::prelude::*;
 
-use super::Processor;
+use super::{sys_proactor, Processor};
 
 use crate::{Handle, Proactor};
 
@@ -24,13 +25,16 @@ impl<T: AsRawFd> Handle<T> {
             store_file: None,
             read: Arc::new(TTas::new(None)),
             write: Arc::new(TTas::new(None)),
+            read_timeout: None,
+            write_timeout: None,
+            proactor: Proactor::current(),
         })
     }
 
     pub(crate) fn new_with_callback(io: T, evflags: i32) -> io::Result<Handle<T>> {
         let fd = io.as_raw_fd();
         let mut handle = Handle::new(io)?;
-        let register = Proactor::get().inner().register_io(fd, evflags)?;
+        let register = sys_proactor().register_io(fd, evflags)?;
         handle.chan = Some(register);
         Ok(handle)
     }
@@ -44,7 +48,12 @@ impl Handle<TcpListener> {
     }
 
     pub async fn accept(&self) -> io::Result<(Handle<TcpStream>, SocketAddr)> {
-        Processor::processor_accept_tcp_listener(self.get_ref()).await
+        self.proactor
+            .route(Processor::processor_accept_tcp_listener(
+                self.get_ref(),
+                self.read_timeout,
+            ))
+            .await
     }
 
     pub fn incoming(
@@ -62,19 +71,54 @@ impl Handle<TcpListener> {
 
 impl Handle<TcpStream> {
     pub async fn connect<A: ToSocketAddrs>(sock_addrs: A) -> io::Result<Handle<TcpStream>> {
-        Ok(Processor::processor_connect(sock_addrs, Processor::processor_connect_tcp).await?)
+        Processor::processor_connect(sock_addrs, |addr| {
+            Processor::processor_connect_tcp(addr, None)
+        })
+        .await
+    }
+
+    ///
+    /// Connects to the given addresses, giving up on each address after the timeout.
+    ///
+    /// Connection attempts that don't complete in time fail with [io::ErrorKind::TimedOut].
+    pub async fn connect_timeout<A: ToSocketAddrs>(
+        sock_addrs: A,
+        timeout: Duration,
+    ) -> io::Result<Handle<TcpStream>> {
+        Processor::processor_connect(sock_addrs, |addr| {
+            Processor::processor_connect_tcp(addr, Some(timeout))
+        })
+        .await
     }
 
     pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
-        Processor::processor_send(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_send(
+                self.get_ref(),
+                buf,
+                self.write_timeout,
+            ))
+            .await
     }
 
     pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_recv(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_recv(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
     }
 
     pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_peek(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_peek(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
     }
 }
 
@@ -88,20 +132,47 @@ impl Handle<UdpSocket> {
     }
 
     pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
-        Processor::processor_send(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_send(
+                self.get_ref(),
+                buf,
+                self.write_timeout,
+            ))
+            .await
     }
 
     pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_recv(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_recv(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
     }
 
     pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_peek(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_peek(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
     }
 
     pub async fn send_to<A: ToSocketAddrs>(&self, buf: &[u8], addr: A) -> io::Result<usize> {
         match addr.to_socket_addrs()?.next() {
-            Some(addr) => Processor::processor_send_to(self.get_ref(), buf, addr).await,
+            Some(addr) => {
+                self.proactor
+                    .route(Processor::processor_send_to(
+                        self.get_ref(),
+                        buf,
+                        addr,
+                        self.write_timeout,
+                    ))
+                    .await
+            }
             None => Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 "given addresses can't be parsed",
@@ -110,11 +181,23 @@ impl Handle<UdpSocket> {
     }
 
     pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
-        Processor::processor_recv_from(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_recv_from(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
     }
 
     pub async fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
-        Processor::processor_peek_from(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_peek_from(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
     }
 }
 
@@ -124,7 +207,12 @@ impl Handle<UnixListener> {
     }
 
     pub async fn accept(&self) -> io::Result<(Handle<UnixStream>, UnixSocketAddr)> {
-        Processor::processor_accept_unix_listener(self.get_ref()).await
+        self.proactor
+            .route(Processor::processor_accept_unix_listener(
+                self.get_ref(),
+                self.read_timeout,
+            ))
+            .await
     }
 
     pub fn incoming(
@@ -156,15 +244,33 @@ impl Handle<UnixStream> {
     }
 
     pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
-        Processor::processor_send(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_send(
+                self.get_ref(),
+                buf,
+                self.write_timeout,
+            ))
+            .await
     }
 
     pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_recv(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_recv(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
     }
 
     pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_peek(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_peek(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
     }
 }
 
@@ -184,26 +290,63 @@ impl Handle<UnixDatagram> {
     }
 
     pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
-        Processor::processor_send(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_send(
+                self.get_ref(),
+                buf,
+                self.write_timeout,
+            ))
+            .await
     }
 
     pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_recv(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_recv(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
     }
 
     pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
-        Processor::processor_peek(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_peek(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
     }
 
     pub async fn send_to<P: AsRef<Path>>(&self, buf: &[u8], path: P) -> io::Result<usize> {
-        Processor::processor_send_to_unix(self.get_ref(), buf, path).await
+        self.proactor
+            .route(Processor::processor_send_to_unix(
+                self.get_ref(),
+                buf,
+                path,
+                self.write_timeout,
+            ))
+            .await
     }
 
     pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, UnixSocketAddr)> {
-        Processor::processor_recv_from_unix(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_recv_from_unix(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
     }
 
     pub async fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, UnixSocketAddr)> {
-        Processor::processor_peek_from_unix(self.get_ref(), buf).await
+        self.proactor
+            .route(Processor::processor_peek_from_unix(
+                self.get_ref(),
+                buf,
+                self.read_timeout,
+            ))
+            .await
     }
 }
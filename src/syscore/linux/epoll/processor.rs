@@ -6,15 +6,42 @@ use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6, UdpSocket};
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::os::unix::net::{SocketAddr as UnixSocketAddr, UnixStream};
 use std::path::Path;
+use std::ptr::null_mut;
+use std::time::Duration;
 use std::{
     fs::File,
-    mem::ManuallyDrop,
+    mem::{ManuallyDrop, MaybeUninit},
     os::unix::io::{AsRawFd, FromRawFd},
 };
 
-use crate::proactor::Proactor;
-use crate::syscore::shim_to_af_unix;
-use crate::Handle;
+use super::{shim_to_af_unix, sys_proactor};
+use crate::{FixedBuf, Handle, OpClass};
+use std::ffi::CString;
+use std::io::{IoSlice, IoSliceMut};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::prelude::RawFd;
+
+macro_rules! syscall {
+    ($fn:ident $args:tt) => {{
+        let res = unsafe { libc::$fn $args };
+        if res == -1 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(res)
+        }
+    }};
+}
+
+/// Accepted sockets don't inherit the non-blocking mode of their listener.
+fn accepted<T: AsRawFd>(stream: T) -> io::Result<Handle<T>> {
+    let flags = syscall!(fcntl(stream.as_raw_fd(), libc::F_GETFL))?;
+    syscall!(fcntl(
+        stream.as_raw_fd(),
+        libc::F_SETFL,
+        flags | libc::O_NONBLOCK
+    ))?;
+    Handle::new(stream)
+}
 
 pub struct Processor;
 
@@ -56,12 +83,209 @@ impl Processor {
         res.map(|e| e as usize)
     }
 
+    ///////////////////////////////////
+    ///// Positional File IO
+    ///// Used when epoll is selected at runtime over io_uring
+    ///////////////////////////////////
+
+    pub(crate) async fn processor_open_at(path: impl AsRef<Path>) -> io::Result<usize> {
+        let path = CString::new(path.as_ref().as_os_str().as_bytes()).expect("invalid path");
+        let fd = syscall!(open(path.as_ptr(), libc::O_CLOEXEC | libc::O_RDONLY, 0o666))?;
+
+        Ok(fd as _)
+    }
+
+    pub(crate) async fn processor_read_file_at(
+        io: &RawFd,
+        buf: &mut [u8],
+        offset: usize,
+    ) -> io::Result<usize> {
+        let res = syscall!(pread(
+            *io,
+            buf.as_mut_ptr() as *mut _,
+            buf.len(),
+            offset as _
+        ))?;
+
+        Ok(res as _)
+    }
+
+    pub(crate) async fn processor_write_file_at(
+        io: &RawFd,
+        buf: &[u8],
+        offset: usize,
+    ) -> io::Result<usize> {
+        let res = syscall!(pwrite(
+            *io,
+            buf.as_ptr() as *const _,
+            buf.len(),
+            offset as _
+        ))?;
+
+        Ok(res as _)
+    }
+
+    pub(crate) async fn processor_read_fixed(
+        io: &RawFd,
+        mut buf: FixedBuf,
+        offset: usize,
+        _timeout: Option<Duration>,
+    ) -> io::Result<(usize, FixedBuf)> {
+        // Regular files are always readable, so they are read without a timeout.
+        let res = syscall!(pread(
+            *io,
+            buf.as_mut_ptr() as *mut _,
+            buf.capacity(),
+            offset as _
+        ))?;
+        buf.set_len(res as _);
+
+        Ok((res as _, buf))
+    }
+
+    pub(crate) async fn processor_write_fixed(
+        io: &RawFd,
+        buf: FixedBuf,
+        offset: usize,
+    ) -> io::Result<(usize, FixedBuf)> {
+        let res = Self::processor_write_file_at(io, &buf, offset).await?;
+
+        Ok((res, buf))
+    }
+
+    pub(crate) async fn processor_close_file(io: &RawFd) -> io::Result<usize> {
+        let res = syscall!(close(*io))?;
+
+        Ok(res as _)
+    }
+
+    pub(crate) async fn processor_file_size(io: &RawFd) -> io::Result<usize> {
+        let mut stat = MaybeUninit::<libc::stat>::zeroed();
+        syscall!(fstat(*io, stat.as_mut_ptr()))?;
+
+        unsafe { Ok(stat.assume_init().st_size as usize) }
+    }
+
+    pub(crate) async fn processor_read_vectored_at(
+        io: &RawFd,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> io::Result<usize> {
+        let res = syscall!(preadv(
+            *io,
+            bufs.as_ptr() as *const libc::iovec,
+            bufs.len() as _,
+            0
+        ))?;
+
+        Ok(res as _)
+    }
+
+    pub(crate) async fn processor_write_vectored_at(
+        io: &RawFd,
+        bufs: &[IoSlice<'_>],
+    ) -> io::Result<usize> {
+        let res = syscall!(pwritev(
+            *io,
+            bufs.as_ptr() as *const libc::iovec,
+            bufs.len() as _,
+            0
+        ))?;
+
+        Ok(res as _)
+    }
+
+    ///////////////////////////////////
+    ///// Splice, Tee
+    ///// One side of the transfer is a pipe
+    ///////////////////////////////////
+
+    // illumos has no splice(2) and tee(2).
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) async fn processor_splice(
+        fd_in: &RawFd,
+        off_in: Option<u64>,
+        fd_out: &RawFd,
+        len: usize,
+    ) -> io::Result<usize> {
+        let mut off = off_in.map(|off| off as libc::loff_t);
+
+        loop {
+            let off_ptr = off.as_mut().map_or(null_mut(), |off| off as *mut _);
+            match syscall!(splice(
+                *fd_in,
+                off_ptr,
+                *fd_out,
+                null_mut(),
+                len,
+                libc::SPLICE_F_NONBLOCK
+            )) {
+                Ok(res) => return Ok(res as _),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    Self::splice_ready(*fd_in, *fd_out).await?
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) async fn processor_tee(
+        fd_in: &RawFd,
+        fd_out: &RawFd,
+        len: usize,
+    ) -> io::Result<usize> {
+        loop {
+            match syscall!(tee(*fd_in, *fd_out, len, libc::SPLICE_F_NONBLOCK)) {
+                Ok(res) => return Ok(res as _),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    Self::splice_ready(*fd_in, *fd_out).await?
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Waits until the side of the transfer that blocked is ready again.
+    async fn splice_ready(fd_in: RawFd, fd_out: RawFd) -> io::Result<()> {
+        let mut fds = [
+            libc::pollfd {
+                fd: fd_in,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: fd_out,
+                events: libc::POLLOUT,
+                revents: 0,
+            },
+        ];
+        syscall!(poll(fds.as_mut_ptr(), fds.len() as _, 0))?;
+
+        let (class, fd, events) = if fds[0].revents == 0 {
+            (OpClass::Read, fd_in, libc::EPOLLIN)
+        } else if fds[1].revents == 0 {
+            (OpClass::Write, fd_out, libc::EPOLLOUT)
+        } else {
+            // Became ready in the meantime, errors surface on the retry.
+            return Ok(());
+        };
+        sys_proactor()
+            .register_op(class, fd, events as _, None)?
+            .await?;
+
+        Ok(())
+    }
+
     ///////////////////////////////////
     ///// Send, Recv, Peek
     ///// Commonality of TcpStream, UdpSocket, UnixStream, UnixDatagram
     ///////////////////////////////////
 
-    pub(crate) async fn processor_send<R: AsRawFd>(socket: &R, buf: &[u8]) -> io::Result<usize> {
+    pub(crate) async fn processor_send<R: AsRawFd>(
+        socket: &R,
+        buf: &[u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
         let sock = unsafe { socket2::Socket::from_raw_fd(socket.as_raw_fd()) };
         let sock = ManuallyDrop::new(sock);
 
@@ -69,9 +293,12 @@ impl Processor {
         match sock.send(buf) {
             Ok(res) => Ok(res),
             Err(err) if (err.raw_os_error().unwrap() & (libc::EAGAIN | libc::EWOULDBLOCK)) != 0 => {
-                let cc = Proactor::get()
-                    .inner()
-                    .register_io(socket.as_raw_fd(), libc::EPOLLIN as i32)?;
+                let cc = sys_proactor().register_op(
+                    OpClass::Send,
+                    socket.as_raw_fd(),
+                    libc::EPOLLIN as i32,
+                    timeout,
+                )?;
                 let events = cc.await?;
                 if (events & libc::EPOLLERR as i32) != 0 {
                     Err(sock.take_error()?.unwrap())
@@ -83,18 +310,50 @@ impl Processor {
         }
     }
 
-    pub(crate) async fn processor_recv<R: AsRawFd>(sock: &R, buf: &mut [u8]) -> io::Result<usize> {
-        Self::recv_with_flags(sock, buf, 0).await
+    pub(crate) async fn processor_recv<R: AsRawFd>(
+        sock: &R,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        Self::recv_with_flags(sock, buf, 0, timeout).await
+    }
+
+    pub(crate) async fn processor_peek<R: AsRawFd>(
+        sock: &R,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        Self::recv_with_flags(sock, buf, libc::MSG_PEEK as _, timeout).await
+    }
+
+    pub(crate) async fn processor_send_fixed<R: AsRawFd>(
+        socket: &R,
+        buf: FixedBuf,
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, FixedBuf)> {
+        let res = Self::processor_send(socket, &buf, timeout).await?;
+
+        Ok((res, buf))
     }
 
-    pub(crate) async fn processor_peek<R: AsRawFd>(sock: &R, buf: &mut [u8]) -> io::Result<usize> {
-        Self::recv_with_flags(sock, buf, libc::MSG_PEEK as _).await
+    pub(crate) async fn processor_recv_fixed<R: AsRawFd>(
+        socket: &R,
+        mut buf: FixedBuf,
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, FixedBuf)> {
+        // Buffers aren't registered on epoll, they are received into as they are.
+        let spare = unsafe { std::slice::from_raw_parts_mut(buf.as_mut_ptr(), buf.capacity()) };
+        let res = Self::processor_recv(socket, spare, timeout).await?;
+        buf.set_len(res);
+
+        Ok((res, buf))
     }
 
     async fn recv_with_flags<R: AsRawFd>(
         socket: &R,
         buf: &mut [u8],
         flags: u32,
+        timeout: Option<Duration>,
     ) -> io::Result<usize> {
         let sock = unsafe { socket2::Socket::from_raw_fd(socket.as_raw_fd()) };
         let sock = ManuallyDrop::new(sock);
@@ -103,9 +362,12 @@ impl Processor {
         match sock.recv_with_flags(buf, flags as _) {
             Ok(res) => Ok(res),
             Err(err) if (err.raw_os_error().unwrap() & (libc::EAGAIN | libc::EWOULDBLOCK)) != 0 => {
-                let cc = Proactor::get()
-                    .inner()
-                    .register_io(socket.as_raw_fd(), libc::EPOLLIN as _)?;
+                let cc = sys_proactor().register_op(
+                    OpClass::Recv,
+                    socket.as_raw_fd(),
+                    libc::EPOLLIN as _,
+                    timeout,
+                )?;
                 let events = cc.await?;
                 if (events & libc::EPOLLERR as i32) != 0 {
                     Err(sock.take_error()?.unwrap())
@@ -149,7 +411,10 @@ impl Processor {
         }))
     }
 
-    pub(crate) async fn processor_connect_tcp(addr: SocketAddr) -> io::Result<Handle<TcpStream>> {
+    pub(crate) async fn processor_connect_tcp(
+        addr: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> io::Result<Handle<TcpStream>> {
         let addr = addr.to_string();
         // FIXME: address resolution is always blocking.
         let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
@@ -168,25 +433,31 @@ impl Processor {
             Some(socket2::Protocol::tcp()),
         )?;
 
-        // Begin async connect and ignore the inevitable "in progress" error.
+        // Begin async connect and remember the inevitable "in progress" error.
         sock.set_nonblocking(true)?;
-        sock.connect(&addr.into()).or_else(|err| {
-            let in_progress = err.raw_os_error() == Some(libc::EINPROGRESS);
-
+        let in_progress = match sock.connect(&addr.into()) {
+            Ok(()) => false,
             // If connect results with an "in progress" error, that's not an error.
-            if in_progress {
-                Ok(())
-            } else {
-                Err(err)
-            }
-        })?;
+            Err(err) if err.raw_os_error() == Some(libc::EINPROGRESS) => true,
+            Err(err) => return Err(err),
+        };
 
         let stream_raw = sock.into_tcp_stream();
         stream_raw.set_nodelay(true)?;
 
         let stream = Handle::new(stream_raw)?;
 
-        // TODO: Recurse here on connect purpose
+        // Socket becomes writable when the connection is established or failed.
+        if in_progress {
+            sys_proactor()
+                .register_op(
+                    OpClass::Connect,
+                    stream.as_raw_fd(),
+                    libc::EPOLLOUT as _,
+                    timeout,
+                )?
+                .await?;
+        }
 
         match stream.get_ref().take_error()? {
             None => Ok(stream),
@@ -234,6 +505,7 @@ impl Processor {
 
     pub(crate) async fn processor_accept_tcp_listener<R: AsRawFd>(
         listener: &R,
+        timeout: Option<Duration>,
     ) -> io::Result<(Handle<TcpStream>, SocketAddr)> {
         let socket = unsafe { socket2::Socket::from_raw_fd(listener.as_raw_fd()) };
         let socket = socket.into_tcp_listener();
@@ -242,20 +514,23 @@ impl Processor {
         // Reregister on block
         match socket
             .accept()
-            .map(|(stream, sockaddr)| (Handle::new(stream).unwrap(), sockaddr))
+            .and_then(|(stream, sockaddr)| Ok((accepted(stream)?, sockaddr)))
         {
             Ok(res) => Ok(res),
             Err(err) if (err.raw_os_error().unwrap() & (libc::EAGAIN | libc::EWOULDBLOCK)) != 0 => {
-                let cc = Proactor::get()
-                    .inner()
-                    .register_io(listener.as_raw_fd(), libc::EPOLLIN as _)?;
+                let cc = sys_proactor().register_op(
+                    OpClass::Accept,
+                    listener.as_raw_fd(),
+                    libc::EPOLLIN as _,
+                    timeout,
+                )?;
                 let events = cc.await?;
                 if (events & libc::EPOLLERR as i32) != 0 {
                     Err(socket.take_error()?.unwrap())
                 } else {
                     socket
                         .accept()
-                        .map(|(stream, sockaddr)| (Handle::new(stream).unwrap(), sockaddr))
+                        .and_then(|(stream, sockaddr)| Ok((accepted(stream)?, sockaddr)))
                 }
             }
             Err(e) => Err(e),
@@ -270,14 +545,16 @@ impl Processor {
         socket: &R,
         buf: &[u8],
         addr: SocketAddr,
+        timeout: Option<Duration>,
     ) -> io::Result<usize> {
-        Self::send_to_dest(socket, buf, &socket2::SockAddr::from(addr)).await
+        Self::send_to_dest(socket, buf, &socket2::SockAddr::from(addr), timeout).await
     }
 
     async fn send_to_dest<A: AsRawFd>(
         socket: &A,
         buf: &[u8],
         addr: &socket2::SockAddr,
+        timeout: Option<Duration>,
     ) -> io::Result<usize> {
         let sock = unsafe { socket2::Socket::from_raw_fd(socket.as_raw_fd()) };
         let sock = ManuallyDrop::new(sock);
@@ -286,9 +563,12 @@ impl Processor {
         match sock.send_to(buf, addr) {
             Ok(res) => Ok(res),
             Err(err) if (err.raw_os_error().unwrap() & (libc::EAGAIN | libc::EWOULDBLOCK)) != 0 => {
-                let cc = Proactor::get()
-                    .inner()
-                    .register_io(socket.as_raw_fd(), libc::EPOLLIN as _)?;
+                let cc = sys_proactor().register_op(
+                    OpClass::Send,
+                    socket.as_raw_fd(),
+                    libc::EPOLLIN as _,
+                    timeout,
+                )?;
                 let events = cc.await?;
                 if (events & libc::EPOLLERR as i32) != 0 {
                     Err(sock.take_error()?.unwrap())
@@ -303,8 +583,9 @@ impl Processor {
     pub(crate) async fn processor_recv_from<R: AsRawFd>(
         sock: &R,
         buf: &mut [u8],
+        timeout: Option<Duration>,
     ) -> io::Result<(usize, SocketAddr)> {
-        Self::recv_from_with_flags(sock, buf, 0)
+        Self::recv_from_with_flags(sock, buf, 0, timeout)
             .await
             .map(|(size, sockaddr)| (size, sockaddr.as_std().unwrap()))
     }
@@ -312,8 +593,9 @@ impl Processor {
     pub(crate) async fn processor_peek_from<R: AsRawFd>(
         sock: &R,
         buf: &mut [u8],
+        timeout: Option<Duration>,
     ) -> io::Result<(usize, SocketAddr)> {
-        Self::recv_from_with_flags(sock, buf, libc::MSG_PEEK as _)
+        Self::recv_from_with_flags(sock, buf, libc::MSG_PEEK as _, timeout)
             .await
             .map(|(size, sockaddr)| (size, sockaddr.as_std().unwrap()))
     }
@@ -322,28 +604,27 @@ impl Processor {
         socket: &R,
         buf: &mut [u8],
         flags: u32,
+        timeout: Option<Duration>,
     ) -> io::Result<(usize, socket2::SockAddr)> {
+        // Socket stays owned by its handle, it isn't closed when the receive is done.
         let sock = unsafe { socket2::Socket::from_raw_fd(socket.as_raw_fd()) };
-        // let sockpass = unsafe { socket2::Socket::from_raw_fd(socket.as_raw_fd()) };
-        // let sock = ManuallyDrop::new(sock);
+        let sock = ManuallyDrop::new(sock);
 
         // Reregister on block
-        match super::shim_recv_from(sock, buf, flags as _).map(|(size, sockaddr)| (size, sockaddr))
-        {
+        match super::shim_recv_from(socket.as_raw_fd(), buf, flags as _) {
             Ok(res) => Ok(res),
             Err(err) if (err.raw_os_error().unwrap() & (libc::EAGAIN | libc::EWOULDBLOCK)) != 0 => {
-                let cc = Proactor::get()
-                    .inner()
-                    .register_io(socket.as_raw_fd(), libc::EPOLLIN as _)?;
+                let cc = sys_proactor().register_op(
+                    OpClass::Recv,
+                    socket.as_raw_fd(),
+                    libc::EPOLLIN as _,
+                    timeout,
+                )?;
                 let events = cc.await?;
                 if (events & libc::EPOLLERR as i32) != 0 {
-                    let sock = unsafe { socket2::Socket::from_raw_fd(socket.as_raw_fd()) };
-                    let sock = ManuallyDrop::new(sock);
                     Err(sock.take_error()?.unwrap())
                 } else {
-                    let sock = unsafe { socket2::Socket::from_raw_fd(socket.as_raw_fd()) };
-                    super::shim_recv_from(sock, buf, flags as _)
-                        .map(|(size, sockaddr)| (size, sockaddr))
+                    super::shim_recv_from(socket.as_raw_fd(), buf, flags as _)
                 }
             }
             Err(e) => Err(e),
@@ -356,6 +637,7 @@ impl Processor {
 
     pub(crate) async fn processor_accept_unix_listener<R: AsRawFd>(
         listener: &R,
+        timeout: Option<Duration>,
     ) -> io::Result<(Handle<UnixStream>, UnixSocketAddr)> {
         let socket = unsafe { socket2::Socket::from_raw_fd(listener.as_raw_fd()) };
         let socket = socket.into_unix_listener();
@@ -364,20 +646,23 @@ impl Processor {
         // Reregister on block
         match socket
             .accept()
-            .map(|(stream, sockaddr)| (Handle::new(stream).unwrap(), sockaddr))
+            .and_then(|(stream, sockaddr)| Ok((accepted(stream)?, sockaddr)))
         {
             Ok(res) => Ok(res),
             Err(err) if (err.raw_os_error().unwrap() & (libc::EAGAIN | libc::EWOULDBLOCK)) != 0 => {
-                let cc = Proactor::get()
-                    .inner()
-                    .register_io(socket.as_raw_fd(), libc::EPOLLIN as _)?;
+                let cc = sys_proactor().register_op(
+                    OpClass::Accept,
+                    socket.as_raw_fd(),
+                    libc::EPOLLIN as _,
+                    timeout,
+                )?;
                 let events = cc.await?;
                 if (events & libc::EPOLLERR as i32) != 0 {
                     Err(socket.take_error()?.unwrap())
                 } else {
                     socket
                         .accept()
-                        .map(|(stream, sockaddr)| (Handle::new(stream).unwrap(), sockaddr))
+                        .and_then(|(stream, sockaddr)| Ok((accepted(stream)?, sockaddr)))
                 }
             }
             Err(e) => Err(e),
@@ -404,9 +689,12 @@ impl Processor {
         let res = match sock.connect(&sockaddr) {
             Ok(res) => Ok(res),
             Err(err) if (err.raw_os_error().unwrap() & (libc::EAGAIN | libc::EINPROGRESS)) != 0 => {
-                let cc = Proactor::get()
-                    .inner()
-                    .register_io(stream.as_raw_fd(), libc::EPOLLOUT as _)?;
+                let cc = sys_proactor().register_op(
+                    OpClass::Connect,
+                    stream.as_raw_fd(),
+                    libc::EPOLLOUT as _,
+                    None,
+                )?;
                 let events = cc.await?;
                 if (events & libc::EPOLLERR as i32) != 0 {
                     Err(sock.take_error()?.unwrap())
@@ -424,15 +712,17 @@ impl Processor {
         socket: &R,
         buf: &[u8],
         path: P,
+        timeout: Option<Duration>,
     ) -> io::Result<usize> {
-        Self::send_to_dest(socket, buf, &socket2::SockAddr::unix(path)?).await
+        Self::send_to_dest(socket, buf, &socket2::SockAddr::unix(path)?, timeout).await
     }
 
     pub(crate) async fn processor_recv_from_unix<R: AsRawFd>(
         socket: &R,
         buf: &mut [u8],
+        timeout: Option<Duration>,
     ) -> io::Result<(usize, UnixSocketAddr)> {
-        Self::recv_from_with_flags(socket, buf, 0)
+        Self::recv_from_with_flags(socket, buf, 0, timeout)
             .await
             .map(|(size, sockaddr)| (size, shim_to_af_unix(&sockaddr).unwrap()))
     }
@@ -440,8 +730,9 @@ impl Processor {
     pub(crate) async fn processor_peek_from_unix<R: AsRawFd>(
         socket: &R,
         buf: &mut [u8],
+        timeout: Option<Duration>,
     ) -> io::Result<(usize, UnixSocketAddr)> {
-        Self::recv_from_with_flags(socket, buf, libc::MSG_PEEK as _)
+        Self::recv_from_with_flags(socket, buf, libc::MSG_PEEK as _, timeout)
             .await
             .map(|(size, sockaddr)| (size, shim_to_af_unix(&sockaddr).unwrap()))
     }
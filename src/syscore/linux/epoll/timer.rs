@@ -0,0 +1,90 @@
+use super::{epoll_of, CompletionChan};
+use crate::Proactor;
+use std::fs::File;
+use std::future::Future;
+use std::io::{self, Read};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+macro_rules! syscall {
+    ($fn:ident $args:tt) => {{
+        let res = unsafe { libc::$fn $args };
+        if res == -1 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(res)
+        }
+    }};
+}
+
+fn timespec(duration: Duration) -> libc::timespec {
+    libc::timespec {
+        tv_sec: duration.as_secs() as _,
+        tv_nsec: duration.subsec_nanos() as _,
+    }
+}
+
+///
+/// Timer that is driven by a timerfd registered to the epoll proactor.
+pub(crate) struct Timer {
+    proactor: Proactor,
+    tfd: File,
+    chan: Option<CompletionChan>,
+}
+
+impl Timer {
+    pub(crate) fn new(duration: Duration, periodic: bool) -> io::Result<Timer> {
+        let fd = syscall!(timerfd_create(
+            libc::CLOCK_MONOTONIC,
+            libc::TFD_NONBLOCK | libc::TFD_CLOEXEC
+        ))?;
+        let tfd = unsafe { File::from_raw_fd(fd) };
+
+        // Zeroed expiration disarms the timer, expire right away instead.
+        let duration = duration.max(Duration::from_nanos(1));
+        let spec = libc::itimerspec {
+            it_interval: timespec(if periodic { duration } else { Duration::ZERO }),
+            it_value: timespec(duration),
+        };
+        syscall!(timerfd_settime(fd, 0, &spec, std::ptr::null_mut()))?;
+
+        Ok(Timer {
+            proactor: Proactor::current(),
+            tfd,
+            chan: None,
+        })
+    }
+
+    pub(crate) fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            if let Some(chan) = self.chan.as_mut() {
+                futures::ready!(Pin::new(chan).poll(cx))?;
+                self.chan = None;
+            }
+
+            // Expiration count, missed ticks are coalesced.
+            let mut expirations = [0_u8; 8];
+            match self.tfd.read(&mut expirations) {
+                Ok(_) => return Poll::Ready(Ok(())),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    self.chan = Some(
+                        epoll_of(self.proactor.inner())
+                            .register_io(self.tfd.as_raw_fd(), libc::EPOLLIN as _)?,
+                    );
+                }
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        // Interest would outlive the timerfd otherwise.
+        if self.chan.take().is_some() {
+            epoll_of(self.proactor.inner()).deregister_io(self.tfd.as_raw_fd());
+        }
+    }
+}
@@ -47,6 +47,20 @@ impl Cancellation {
         }
     }
 
+    /// Whether the cancellation holds no resources.
+    pub(crate) fn is_null(&self) -> bool {
+        self.data.is_null()
+    }
+
+    /// Construct a cancellation that owns the boxed value, and drops it when the event concludes.
+    pub(crate) fn boxed<T: Send>(data: Box<T>) -> Cancellation {
+        unsafe fn drop<T>(data: *mut (), _: usize) {
+            std::mem::drop(Box::from_raw(data as *mut T))
+        }
+
+        unsafe { Cancellation::new(Box::into_raw(data) as *mut (), 0, drop::<T>) }
+    }
+
     pub(crate) unsafe fn buffer(data: *mut u8, len: usize) -> Cancellation {
         unsafe fn drop(data: *mut (), len: usize) {
             std::mem::drop(Vec::from_raw_parts(data as *mut u8, len, len))
@@ -1,4 +1,5 @@
 use std::fs::File;
+use std::future::Future;
 use std::io;
 use std::os::unix::io::{FromRawFd, RawFd};
 use std::pin::Pin;
@@ -6,6 +7,7 @@ use std::sync::Arc;
 
 use super::buffer::Buffer;
 use crate::syscore::Processor;
+use crate::Proactor;
 use lever::sync::atomics::AtomicBox;
 
 pub struct StoreFile {
@@ -14,6 +16,8 @@ pub struct StoreFile {
     pub internal: Vec<u8>,
     op_state: Arc<AtomicBox<Op>>,
     pos: usize,
+    /// Proactor that the buffer is lent to.
+    proactor: Proactor,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -35,6 +39,7 @@ impl StoreFile {
             buf: Buffer::new(),
             internal: Vec::with_capacity(8192),
             pos: 0,
+            proactor: Proactor::current(),
         }
     }
 
@@ -64,13 +69,13 @@ impl StoreFile {
         }
     }
 
-    pub(crate) async fn poll_file_size(&mut self) -> io::Result<usize> {
+    pub(crate) fn poll_file_size(&mut self) -> impl Future<Output = io::Result<usize>> + 'static {
         self.op_state().replace_with(|_| Op::Statx);
         let fd = self.receive_fd();
         let (buf, _) = self.bufpair();
         let statx = buf.as_statx();
 
-        Processor::processor_file_size(&fd, statx).await
+        async move { Processor::processor_file_size(&fd, statx).await }
     }
 
     #[inline(always)]
@@ -98,7 +103,9 @@ impl StoreFile {
 
     pub(crate) fn cancel(&mut self) {
         self.op_state.replace_with(|_| Op::Nothing);
-        self.buf.cancellation();
+        // Cancelled operations might still be filling the buffer until the kernel acknowledges them.
+        let resources = self.buf.cancellation();
+        self.proactor.inner().release_after_cancelled(resources);
     }
 }
 
@@ -1,16 +1,20 @@
 use ahash::{HashMap, HashMapExt};
 use core::mem::MaybeUninit;
 
+use futures::task::AtomicWaker;
 use lever::sync::prelude::*;
-use pin_utils::unsafe_pinned;
 use std::future::Future;
 
+use std::cell::Cell;
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
 use std::io;
-use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::pin::Pin;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 macro_rules! syscall {
     ($fn:ident $args:tt) => {{
@@ -26,13 +30,29 @@ macro_rules! syscall {
 ///////////////////
 ///////////////////
 
-use crate::config::NucleiConfig;
-use crossbeam_channel::{unbounded, Receiver, Sender};
+use super::fs::cancellation::Cancellation;
+use super::net::buf_ring::{BufRing, RECV_BGID};
+use crate::config::{
+    IoUringConfiguration, NucleiConfig, SubmissionBackpressure, SubmissionBatching,
+};
+use crate::driver::Driver;
+use crate::latency::{Latencies, LatencyHistogram, OpClass};
+use crate::proactor::{
+    Capabilities, Current, InflightOperation, Proactor, ProactorStats, ShutdownMode,
+};
+use crate::sys::IoBackend;
+use crate::syscore::ShutdownState;
+use crate::{EventHook, OpEvent, OpEventKind};
+use crossbeam_channel::{unbounded, Receiver, Sender, TryRecvError};
+use once_cell::sync::OnceCell;
 
-use rustix_uring::cqueue::{more, sock_nonempty};
+use rustix_uring::cqueue::{buffer_select, more, sock_nonempty, Flags as CQFlags};
+use rustix_uring::opcode as OP;
+use rustix_uring::squeue::Flags;
+use rustix_uring::types::{Fixed, SubmitArgs, Timespec};
 use rustix_uring::{
-    cqueue::Entry as CQEntry, squeue::Entry as SQEntry, CompletionQueue, IoUring, SubmissionQueue,
-    Submitter,
+    cqueue::Entry as CQEntry, squeue::Entry as SQEntry, CompletionQueue, IoUring, Parameters,
+    Probe, SubmissionQueue, Submitter,
 };
 use socket2::SockAddr;
 use std::mem;
@@ -140,42 +160,312 @@ pub(crate) fn shim_to_af_unix(sockaddr: &SockAddr) -> io::Result<UnixSocketAddr>
 /// uring impl
 ///////////////////
 
+///
+/// In-flight submission bookkeeping, keyed by `user_data` in [SysProactor].
+struct InflightOp {
+    /// Completion results and flags of the submission, more than one for multishot operations.
+    tx: Sender<(i32, CQFlags)>,
+    /// Waker of the task awaiting the completion.
+    waker: Arc<AtomicWaker>,
+    /// Whether the awaiting future is dropped and cancellation is requested.
+    cancelled: bool,
+    /// Resources that kernel might still access after the awaiting future is dropped.
+    /// They are released with the final completion of the operation.
+    resources: Option<Cancellation>,
+    opcode: u8,
+    fd: RawFd,
+    /// When the submission is pushed.
+    submitted_at: Instant,
+    /// Whether a multishot completion is posted, later ones aren't tied to the submission.
+    reposted: bool,
+}
+
+impl InflightOp {
+    fn complete(&self, res: i32, flags: CQFlags) {
+        let _ = self.tx.send((res, flags));
+        self.waker.wake();
+    }
+
+    fn event(&self, kind: OpEventKind, id: u64) -> OpEvent {
+        OpEvent {
+            kind,
+            opcode: Some(self.opcode),
+            class: op_class(self.opcode),
+            fd: self.fd,
+            user_data: id,
+            result: 0,
+            bytes: 0,
+        }
+    }
+}
+
+/// Opcode and file descriptor of the submission.
+fn sqe_target(sqe: &SQEntry) -> (u8, RawFd) {
+    // Entries don't expose them, they lead the `io_uring_sqe` layout.
+    let raw = sqe as *const SQEntry as *const u8;
+    unsafe { (*raw, *(raw.add(4) as *const RawFd)) }
+}
+
+/// Whether the operation is a zero-copy send, which completes with two CQEs: the result, and
+/// the notification that the kernel is done with the buffer.
+fn zero_copy(opcode: u8) -> bool {
+    [OP::SendZc::CODE as u8, OP::SendMsgZc::CODE as u8].contains(&opcode)
+}
+
+/// Class of the operation that the opcode is for, [None] if its latency isn't recorded.
+fn op_class(opcode: u8) -> Option<OpClass> {
+    let is = |codes: &[u8]| codes.contains(&opcode);
+
+    if is(&[
+        OP::Read::CODE as _,
+        OP::Readv::CODE as _,
+        OP::ReadFixed::CODE as _,
+    ]) {
+        Some(OpClass::Read)
+    } else if is(&[
+        OP::Write::CODE as _,
+        OP::Writev::CODE as _,
+        OP::WriteFixed::CODE as _,
+    ]) {
+        Some(OpClass::Write)
+    } else if is(&[OP::Recv::CODE as _, OP::RecvMsg::CODE as _]) {
+        Some(OpClass::Recv)
+    } else if is(&[
+        OP::Send::CODE as _,
+        OP::SendMsg::CODE as _,
+        OP::SendZc::CODE as _,
+        OP::SendMsgZc::CODE as _,
+    ]) {
+        Some(OpClass::Send)
+    } else if is(&[OP::Accept::CODE as _]) {
+        Some(OpClass::Accept)
+    } else if is(&[OP::Connect::CODE as _]) {
+        Some(OpClass::Connect)
+    } else if is(&[OP::Statx::CODE as _]) {
+        Some(OpClass::Statx)
+    } else {
+        None
+    }
+}
+
+/// Sets the ring up with the flags of the configuration.
+///
+/// Flag combinations that the kernel refuses are rejected upfront, and flags that the kernel
+/// doesn't know are reported by name instead of a bare `EINVAL`.
+fn build_ring(config: &IoUringConfiguration) -> io::Result<IoUring> {
+    let invalid = |msg: &str| Err(io::Error::new(io::ErrorKind::InvalidInput, msg.to_owned()));
+    if config.sqpoll_cpu.is_some() && config.sqpoll_wake_interval.is_none() {
+        return invalid("nuclei: io_uring `sqpoll_cpu` requires `sqpoll_wake_interval`");
+    }
+    if config.defer_taskrun && !config.single_issuer {
+        return invalid("nuclei: io_uring `defer_taskrun` requires `single_issuer`");
+    }
+    if config.defer_taskrun && config.sqpoll_wake_interval.is_some() {
+        return invalid("nuclei: io_uring `defer_taskrun` can't be combined with SQPOLL");
+    }
+    if config
+        .cq_len
+        .is_some_and(|cq_len| cq_len < config.queue_len)
+    {
+        return invalid("nuclei: io_uring `cq_len` can't be less than `queue_len`");
+    }
+
+    let mut rb = IoUring::builder();
+    config.sqpoll_wake_interval.map(|e| rb.setup_sqpoll(e));
+    config.sqpoll_cpu.map(|cpu| rb.setup_sqpoll_cpu(cpu));
+    config.cq_len.map(|cq_len| rb.setup_cqsize(cq_len));
+    config.attach_wq.map(|fd| rb.setup_attach_wq(fd));
+    if config.iopoll_enabled {
+        rb.setup_iopoll();
+    }
+    if config.clamp {
+        rb.setup_clamp();
+    }
+    if config.single_issuer {
+        rb.setup_single_issuer();
+    }
+    if config.coop_taskrun {
+        rb.setup_coop_taskrun();
+    }
+    if config.defer_taskrun {
+        rb.setup_defer_taskrun();
+    }
+    if config.submit_all {
+        rb.setup_submit_all();
+    }
+
+    // Ring setup fails when io_uring is unsupported or disabled (seccomp, io_uring_disabled sysctl).
+    let ring = rb.build(config.queue_len).map_err(|e| {
+        let flags: Vec<&str> = [
+            (config.single_issuer, "single_issuer"),
+            (config.coop_taskrun, "coop_taskrun"),
+            (config.defer_taskrun, "defer_taskrun"),
+            (config.submit_all, "submit_all"),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .collect();
+
+        if e == rustix::io::Errno::INVAL && !flags.is_empty() {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "nuclei: io_uring setup failed, kernel might not support `{}`: {}",
+                    flags.join("`, `"),
+                    e
+                ),
+            )
+        } else {
+            e.into()
+        }
+    })?;
+
+    let params = ring.params();
+    if config.single_issuer && !params.is_setup_single_issuer() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "nuclei: io_uring `single_issuer` is not in effect",
+        ));
+    }
+    if !config.clamp
+        && config
+            .cq_len
+            .is_some_and(|cq_len| params.cq_entries() < cq_len)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "nuclei: io_uring completion queue is smaller than `cq_len`",
+        ));
+    }
+    if config.attach_wq.is_some() && !params.is_feature_native_workers() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "nuclei: io_uring `attach_wq` requires native workers (5.12+)",
+        ));
+    }
+
+    Ok(ring)
+}
+
+/// Probes the opcodes that the kernel supports.
+///
+/// Buffer rings and sparse file tables are probed by registering a single entry of them.
+/// Multishot variants share opcodes with their predecessors, so they are told apart by the
+/// opcodes that are introduced in the same kernel release.
+fn probe_capabilities(ring: &IoUring) -> Capabilities {
+    let params = ring.params();
+    let mut probe = Probe::new();
+    if ring.submitter().register_probe(&mut probe).is_err() {
+        // Probing is 5.6+, older kernels don't have any of the operations below either.
+        return Capabilities {
+            fast_poll: params.is_feature_fast_poll(),
+            ext_arg: params.is_feature_ext_arg(),
+            ..Capabilities::default()
+        };
+    }
+
+    // 5.19, along with multishot accept.
+    let socket = probe.is_supported(OP::Socket::CODE);
+    // 6.0, along with multishot receive.
+    let send_zc = probe.is_supported(OP::SendZc::CODE);
+
+    let sbmt = ring.submitter();
+    let buf_ring = BufRing::new(1, 1).is_ok_and(|probe| {
+        let registered =
+            unsafe { sbmt.register_buf_ring(probe.ring_addr(), probe.entries(), RECV_BGID) };
+        registered.is_ok() && sbmt.unregister_buf_ring(RECV_BGID).is_ok()
+    });
+    // Sparse tables are introduced along with the slots that the kernel allocates.
+    let direct_files = sbmt.register_files_sparse(1).is_ok() && sbmt.unregister_files().is_ok();
+
+    Capabilities {
+        accept_multi: socket,
+        recv_multi: send_zc,
+        send_zc,
+        sendmsg_zc: probe.is_supported(OP::SendMsgZc::CODE),
+        socket,
+        buf_ring,
+        direct_files,
+        provide_buffers: probe.is_supported(OP::ProvideBuffers::CODE),
+        splice: probe.is_supported(OP::Splice::CODE),
+        tee: probe.is_supported(OP::Tee::CODE),
+        fast_poll: params.is_feature_fast_poll(),
+        ext_arg: params.is_feature_ext_arg(),
+    }
+}
+
+/// `user_data` of the internal submissions like cancellations and linked timeouts,
+/// their completions are not dispatched.
+const INTERNAL_USER_DATA: u64 = u64::MAX;
+
+/// Longest time the driver waits for completions before flushing batched submissions.
+const DRIVER_TICK: Duration = Duration::from_millis(1);
+
+thread_local! {
+    /// Address of the proactor that the thread dispatches completions of, wakers fire meanwhile.
+    static DISPATCHING: Cell<usize> = const { Cell::new(0) };
+}
+
+/// io_uring proactor of the current Nuclei instance.
+pub(crate) fn sys_proactor() -> Current<SysProactor> {
+    Current::new(|p| p.uring())
+}
+
 pub struct SysProactor {
     pub(crate) sq: TTas<SubmissionQueue<'static>>,
     pub(crate) cq: TTas<CompletionQueue<'static>>,
     sbmt: Submitter<'static>,
-    submitters: TTas<HashMap<u64, Sender<i32>>>,
+    submitters: TTas<HashMap<u64, InflightOp>>,
     submitter_id: AtomicU64,
     aggressive_poll: bool,
+    backpressure: SubmissionBackpressure,
+    /// Submissions parked by [SubmissionBackpressure::Overflow] while the submission queue is full.
+    overflow: TTas<VecDeque<Vec<SQEntry>>>,
+    sq_full_events: AtomicU64,
+    sqes_submitted: AtomicU64,
+    cqes_reaped: AtomicU64,
+    /// Overflow counter of the completion queue, as of the last time it is synced.
+    cq_overflow: AtomicU64,
+    wakeups: AtomicU64,
+    latencies: Latencies,
+    hook: Option<Arc<dyn EventHook>>,
+    batching: SubmissionBatching,
+    /// Submissions queued since the last time kernel was entered.
+    pending: AtomicU32,
+    /// Operations that are cancelled but not yet acknowledged by the kernel.
+    cancelled: TTas<BTreeSet<u64>>,
+    /// Resources released once the cancelled operations submitted before them are acknowledged.
+    graveyard: TTas<Vec<(u64, Cancellation)>>,
+    state: ShutdownState,
+    driver: Driver,
+    capabilities: Capabilities,
+    /// Provided buffer ring of the multishot receives, registered with the first of them.
+    buf_ring: OnceCell<BufRing>,
+    recv_buf_ring_len: u32,
+    recv_buf_size: u32,
+    /// Slots of the direct descriptors, set when the sparse file table is registered.
+    direct: OnceCell<TTas<HashMap<RawFd, u32>>>,
+    file_table_len: u32,
+    /// Ring that the queues and the submitter above borrow, so it is declared (and dropped) last.
+    ring: Box<IoUring>,
 }
 
+// Queues and the submitter point into the ring mappings, they are only used behind their locks.
+unsafe impl Send for SysProactor {}
+unsafe impl Sync for SysProactor {}
+
 pub type RingTypes = (
     SubmissionQueue<'static>,
     CompletionQueue<'static>,
     Submitter<'static>,
 );
 
-pub(crate) static mut IO_URING: Option<IoUring> = None;
-
 impl SysProactor {
     pub(crate) fn new(config: NucleiConfig) -> io::Result<SysProactor> {
         unsafe {
-            let mut rb = IoUring::builder();
-            config
-                .iouring
-                .sqpoll_wake_interval
-                .map(|e| rb.setup_sqpoll(e));
-            if config.iouring.iopoll_enabled {
-                rb.setup_iopoll();
-            }
-            let ring = rb
-                .build(config.iouring.queue_len)
-                .expect("nuclei: uring can't be initialized");
-
-            IO_URING = Some(ring);
-
-            let (sbmt, sq, cq) = IO_URING.as_mut().unwrap().split();
+            let ring = build_ring(&config.iouring)?;
 
+            let sbmt = ring.submitter();
             match (
                 config.iouring.per_numa_bounded_worker_count,
                 config.iouring.per_numa_unbounded_worker_count,
@@ -185,6 +475,13 @@ impl SysProactor {
                 (Some(bw), None) => sbmt.register_iowq_max_workers(&mut [bw, 0])?,
                 (None, None) => sbmt.register_iowq_max_workers(&mut [0, 0])?,
             }
+            let capabilities = probe_capabilities(&ring);
+
+            let mut ring = Box::new(ring);
+
+            // Ring is heap allocated and outlives the split parts, see the `ring` field.
+            let ring_ptr: *mut IoUring = &mut *ring;
+            let (sbmt, sq, cq) = (*ring_ptr).split();
 
             Ok(SysProactor {
                 sq: TTas::new(sq),
@@ -193,37 +490,451 @@ impl SysProactor {
                 submitters: TTas::new(HashMap::with_capacity(config.iouring.queue_len as usize)),
                 submitter_id: AtomicU64::default(),
                 aggressive_poll: config.iouring.aggressive_poll,
+                backpressure: config.iouring.backpressure,
+                overflow: TTas::new(VecDeque::new()),
+                sq_full_events: AtomicU64::default(),
+                sqes_submitted: AtomicU64::default(),
+                cqes_reaped: AtomicU64::default(),
+                cq_overflow: AtomicU64::default(),
+                wakeups: AtomicU64::default(),
+                latencies: Latencies::new(),
+                hook: config.event_hook.clone(),
+                batching: config.iouring.batching,
+                pending: AtomicU32::default(),
+                cancelled: TTas::new(BTreeSet::new()),
+                graveyard: TTas::new(Vec::new()),
+                state: ShutdownState::new(),
+                driver: Driver::new(),
+                capabilities,
+                buf_ring: OnceCell::new(),
+                recv_buf_ring_len: config.iouring.recv_buf_ring_len,
+                recv_buf_size: config.iouring.recv_buf_size,
+                direct: OnceCell::new(),
+                file_table_len: config.iouring.file_table_len,
+                ring,
             })
         }
     }
 
+    pub(crate) fn backend(&self) -> IoBackend {
+        IoBackend::IoUring
+    }
+
+    pub(crate) fn params(&self) -> &Parameters {
+        self.ring.params()
+    }
+
+    pub(crate) fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    pub(crate) fn driver(&self) -> &Driver {
+        &self.driver
+    }
+
+    pub(crate) fn ring_fd(&self) -> RawFd {
+        self.ring.as_raw_fd()
+    }
+
     pub(crate) fn register_files_sparse(&self, n: u32) -> io::Result<()> {
         Ok(self.sbmt.register_files_sparse(n)?)
     }
 
-    pub(crate) fn register_io(&self, mut sqe: SQEntry) -> io::Result<CompletionChan> {
+    /// Slots of the direct descriptors, the sparse file table is registered on the first call.
+    pub(crate) fn file_table(&self) -> io::Result<&TTas<HashMap<RawFd, u32>>> {
+        if self.file_table_len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "nuclei: direct descriptors require a file table, see `file_table_len`",
+            ));
+        }
+
+        self.direct.get_or_try_init(|| {
+            self.register_files_sparse(self.file_table_len)?;
+            Ok(TTas::new(HashMap::new()))
+        })
+    }
+
+    /// Slot of the descriptor in the file table, if it is registered as a direct descriptor.
+    ///
+    /// Every operation looks its descriptor up, so the lookup is skipped without a file table and
+    /// before the first direct descriptor is registered.
+    pub(crate) fn direct_slot(&self, fd: RawFd) -> Option<u32> {
+        if self.file_table_len == 0 {
+            return None;
+        }
+
+        self.direct.get()?.lock().get(&fd).copied()
+    }
+
+    /// Closes the slot of the direct descriptor, the descriptor itself is left open.
+    pub(crate) fn close_direct(&self, fd: RawFd) {
+        if let Some(slot) = self.direct.get().and_then(|slots| slots.lock().remove(&fd)) {
+            self.close_slot(slot);
+        }
+    }
+
+    /// Closes the slot in the file table.
+    ///
+    /// Slots are allocated by the kernel, so the slot isn't handed out again before it is closed.
+    pub(crate) fn close_slot(&self, slot: u32) {
+        let sqe = OP::Close::new(Fixed(slot))
+            .build()
+            .user_data(INTERNAL_USER_DATA);
+        let _ = self.submit_entries(&[sqe]);
+    }
+
+    /// Registers the buffers as the fixed buffers of the ring, indexed in the given order.
+    pub(crate) fn register_buffers(&self, bufs: &[libc::iovec]) -> io::Result<()> {
+        // Both are `struct iovec`.
+        let bufs = unsafe { std::slice::from_raw_parts(bufs.as_ptr().cast(), bufs.len()) };
+        Ok(unsafe { self.sbmt.register_buffers(bufs) }?)
+    }
+
+    pub(crate) fn unregister_buffers(&self) -> io::Result<()> {
+        Ok(self.sbmt.unregister_buffers()?)
+    }
+
+    /// Provided buffer ring of the multishot receives, it is registered on the first call.
+    pub(crate) fn recv_buf_ring(&self) -> io::Result<&BufRing> {
+        self.buf_ring.get_or_try_init(|| {
+            let ring = BufRing::new(self.recv_buf_ring_len, self.recv_buf_size)?;
+            unsafe {
+                self.sbmt
+                    .register_buf_ring(ring.ring_addr(), ring.entries(), RECV_BGID)?;
+            }
+            Ok(ring)
+        })
+    }
+
+    /// Completes the operation, buffers that are selected for a cancelled operation go back to
+    /// the ring since nobody receives them.
+    fn complete(&self, op: &InflightOp, res: i32, flags: CQFlags) {
+        let buf_ring = buffer_select(flags).and_then(|bid| Some((bid, self.buf_ring.get()?)));
+        if let Some((bid, buf_ring)) = buf_ring {
+            buf_ring.selected();
+            if op.cancelled {
+                buf_ring.recycle(bid);
+                return;
+            }
+        }
+
+        op.complete(res, flags);
+    }
+
+    pub(crate) fn register_io(&self, sqe: SQEntry) -> io::Result<CompletionChan> {
+        self.register_io_with(sqe, Cancellation::null(), None)
+    }
+
+    /// Registers the submission along with the `resources` it points to.
+    ///
+    /// Resources are kept alive until the completion is awaited, or if the completion future is
+    /// dropped before, until the kernel acknowledges the cancellation of the operation.
+    ///
+    /// Operations without resources might point at memory that the caller only borrowed, so when
+    /// their completion future is dropped early, the drop waits for the kernel to acknowledge the
+    /// cancellation before the borrow ends.
+    ///
+    /// With a `timeout`, the submission is linked with `IORING_OP_LINK_TIMEOUT`, so the kernel
+    /// cancels the operation when the timeout expires, and it completes with [io::ErrorKind::TimedOut].
+    pub(crate) fn register_io_with(
+        &self,
+        mut sqe: SQEntry,
+        resources: Cancellation,
+        timeout: Option<Duration>,
+    ) -> io::Result<CompletionChan> {
+        self.state.check()?;
+
         let id = self.submitter_id.fetch_add(1, Ordering::Relaxed);
-        let (tx, rx) = unbounded::<i32>();
+        let (tx, rx) = unbounded::<(i32, CQFlags)>();
+        let waker = Arc::new(AtomicWaker::new());
 
         sqe = sqe.user_data(id);
+        let (opcode, fd) = sqe_target(&sqe);
 
         let mut subguard = self.submitters.lock();
-        subguard.insert(id, tx);
+        subguard.insert(
+            id,
+            InflightOp {
+                tx,
+                waker: waker.clone(),
+                cancelled: false,
+                resources: None,
+                opcode,
+                fd,
+                submitted_at: Instant::now(),
+                reposted: false,
+            },
+        );
         drop(subguard);
 
+        let borrowed = resources.is_null();
+        let linked;
+        let (entries, resources) = match timeout {
+            Some(timeout) => {
+                // Timespec is read by the kernel when the linked timeout is issued.
+                let held = Box::new((Timespec::from(timeout), resources));
+                let timeout = OP::LinkTimeout::new(&held.0)
+                    .build()
+                    .user_data(INTERNAL_USER_DATA);
+                linked = [sqe.flags(Flags::IO_LINK), timeout];
+                (&linked[..], Cancellation::boxed(held))
+            }
+            None => (std::slice::from_ref(&sqe), resources),
+        };
+
+        // Submit is emitted before the kernel sees the entries, a completion reaped by another
+        // thread can't precede it.
+        let event = OpEvent {
+            kind: OpEventKind::Submit,
+            opcode: Some(opcode),
+            class: op_class(opcode),
+            fd,
+            user_data: id,
+            result: 0,
+            bytes: 0,
+        };
+        self.emit(event);
+
+        if let Err(e) = self.submit_entries(entries) {
+            self.submitters.lock().remove(&id);
+            self.emit(OpEvent {
+                kind: OpEventKind::Error,
+                result: -e.raw_os_error().unwrap_or(libc::EIO),
+                ..event
+            });
+            return Err(e);
+        }
+
+        Ok(CompletionChan {
+            rx,
+            waker,
+            timed: timeout.is_some(),
+            guard: Arc::new(InflightGuard {
+                proactor: Proactor::current(),
+                id,
+                resources,
+                borrowed,
+            }),
+        })
+    }
+
+    fn submit_entries(&self, entries: &[SQEntry]) -> io::Result<()> {
+        self.enqueue(entries)?;
+        self.sqes_submitted
+            .fetch_add(entries.len() as u64, Ordering::Relaxed);
+
+        match self.batching {
+            SubmissionBatching::Immediate => {
+                self.sbmt.submit()?;
+            }
+            SubmissionBatching::Threshold(n) => {
+                // Linked entries count on their own, they all take space in the queue.
+                let queued = entries.len() as u32;
+                if self.pending.fetch_add(queued, Ordering::AcqRel) + queued >= n {
+                    self.submit_pending()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Requests cancellation of the in-flight operation with `IORING_OP_ASYNC_CANCEL`.
+    ///
+    /// `resources` are released right away if the operation already completed,
+    /// otherwise with the final completion of the operation.
+    pub(crate) fn cancel_io(&self, id: u64, resources: Cancellation) {
+        let mut sbmts = self.submitters.lock();
+        let Some(op) = sbmts.get_mut(&id) else {
+            return;
+        };
+
+        op.cancelled = true;
+        op.resources = Some(resources);
+        let event = op.event(OpEventKind::Cancel, id);
+        self.cancelled.lock().insert(id);
+        drop(sbmts);
+        self.emit(event);
+
+        let sqe = OP::AsyncCancel::new(id)
+            .build()
+            .user_data(INTERNAL_USER_DATA);
+        // Kernel will still complete the operation if the cancellation can't be submitted.
+        let _ = self.submit_entries(&[sqe]);
+    }
+
+    /// Requests cancellation of the in-flight operation, and waits until the kernel acknowledges it.
+    ///
+    /// For operations that point at borrowed memory, the kernel mustn't access it once this returns.
+    /// Blocks the thread meanwhile, see the dropping section of [Handle](crate::Handle).
+    pub(crate) fn cancel_io_sync(&self, id: u64) {
+        self.cancel_io(id, Cancellation::null());
+
+        // Reaping submits the batched cancellation, and dispatches the final completion.
+        while self.submitters.lock().contains_key(&id) {
+            if self.reap().is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Releases `resources` once every operation that is cancelled so far is acknowledged,
+    /// for resources that are shared by operations rather than owned by one of them.
+    pub(crate) fn release_after_cancelled(&self, resources: Cancellation) {
+        let tag = self.submitter_id.load(Ordering::Relaxed);
+        let cancelled = self.cancelled.lock();
+        if cancelled.first().is_some_and(|id| *id < tag) {
+            self.graveyard.lock().push((tag, resources));
+        }
+    }
+
+    fn acknowledge_cancel(&self, id: u64) {
+        let mut cancelled = self.cancelled.lock();
+        cancelled.remove(&id);
+        let oldest = cancelled.first().copied();
+        self.graveyard
+            .lock()
+            .retain(|(tag, _)| oldest.is_some_and(|id| id < *tag));
+    }
+
+    /// Enters the kernel if there are batched submissions waiting.
+    fn submit_pending(&self) -> io::Result<()> {
+        if self.pending.swap(0, Ordering::AcqRel) > 0 {
+            self.sbmt.submit()?;
+        }
+
+        Ok(())
+    }
+
+    /// Submits everything queued and waits for at least one completion.
+    ///
+    /// With batching, waiting is bounded by [DRIVER_TICK] so that submissions queued in the
+    /// meantime are not held back until an unrelated completion arrives.
+    fn submit_and_wait(&self) -> io::Result<()> {
+        self.pending.store(0, Ordering::Release);
+        match self.batching {
+            SubmissionBatching::Immediate => {
+                self.sbmt.submit_and_wait(1)?;
+            }
+            SubmissionBatching::Threshold(_) => {
+                let ts = Timespec::from(DRIVER_TICK);
+                let args = SubmitArgs::new().timespec(&ts);
+                match self.sbmt.submit_with_args(1, &args) {
+                    Ok(_) | Err(rustix::io::Errno::TIME) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Number of times a submission found the submission queue full.
+    pub(crate) fn sq_full_events(&self) -> u64 {
+        self.sq_full_events.load(Ordering::Relaxed)
+    }
+
+    /// Enqueues the entries to the submission queue, linked entries are kept together.
+    fn enqueue(&self, entries: &[SQEntry]) -> io::Result<()> {
+        if self.try_push(entries)? {
+            return Ok(());
+        }
+
+        self.sq_full_events.fetch_add(1, Ordering::Relaxed);
+        self.make_room()?;
+        if self.try_push(entries)? {
+            return Ok(());
+        }
+
+        match self.backpressure {
+            // Waker that fires while this thread dispatches completions can't wait for room, the
+            // completion queue is held by the thread itself.
+            SubmissionBackpressure::Wait if self.is_dispatching() => {
+                self.overflow.lock().push_back(entries.to_vec());
+                Ok(())
+            }
+            SubmissionBackpressure::Wait => loop {
+                std::thread::yield_now();
+                self.make_room()?;
+                if self.try_push(entries)? {
+                    return Ok(());
+                }
+            },
+            SubmissionBackpressure::WouldBlock => Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "nuclei: submission queue is full",
+            )),
+            SubmissionBackpressure::Overflow => {
+                self.overflow.lock().push_back(entries.to_vec());
+                Ok(())
+            }
+        }
+    }
+
+    /// Pushes the entries to the submission queue, behind the parked overflow submissions.
+    fn try_push(&self, entries: &[SQEntry]) -> io::Result<bool> {
+        if !self.flush_overflow()? {
+            return Ok(false);
+        }
+
         let mut sq = self.sq.lock();
-        unsafe {
-            sq.push(&sqe).expect("nuclei: submission queue is full");
+        let pushed = unsafe { sq.push_multiple(entries).is_ok() };
+        sq.sync();
+
+        Ok(pushed)
+    }
+
+    /// Moves parked submissions to the submission queue, returns whether the overflow is drained.
+    fn flush_overflow(&self) -> io::Result<bool> {
+        let mut overflow = self.overflow.lock();
+        if overflow.is_empty() {
+            return Ok(true);
+        }
+
+        let mut sq = self.sq.lock();
+        while let Some(entries) = overflow.front() {
+            if unsafe { sq.push_multiple(entries).is_err() } {
+                break;
+            }
+            overflow.pop_front();
         }
         sq.sync();
         drop(sq);
 
         self.sbmt.submit()?;
 
-        Ok(CompletionChan { rx })
+        Ok(overflow.is_empty())
+    }
+
+    /// Hands the queued submissions to the kernel and reaps completions to free queue slots.
+    fn make_room(&self) -> io::Result<()> {
+        match self.sbmt.submit() {
+            // Kernel stops consuming submissions while completion queue is overflown.
+            Ok(_) | Err(rustix::io::Errno::BUSY) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let mut events = Vec::new();
+        if let Some(mut cq) = self.cq.try_lock() {
+            cq.sync();
+            for cqe in cq.by_ref() {
+                self.cqe_completion(&cqe, &mut events);
+            }
+        }
+        events.into_iter().for_each(|event| self.emit(event));
+
+        Ok(())
     }
 
     pub(crate) fn wake(&self) -> io::Result<()> {
+        self.wakeups.fetch_add(1, Ordering::Relaxed);
+        // Completes the wait of a thread that is blocked on the ring.
+        let nop = OP::Nop::new().build().user_data(INTERNAL_USER_DATA);
+        self.enqueue(&[nop])?;
+        self.pending.store(0, Ordering::Release);
+        self.sbmt.submit()?;
+
         Ok(())
     }
 
@@ -231,80 +942,368 @@ impl SysProactor {
         &self,
         max_event_size: usize,
         duration: Option<Duration>,
+        parked: bool,
     ) -> io::Result<usize> {
         let mut cq = self.cq.lock();
         let mut acc: usize = 0;
+        let mut events = Vec::new();
 
         // issue cas barrier
         'sock: loop {
-            if !self.aggressive_poll {
-                self.sbmt.submit_and_wait(1)?;
+            if self.state.is_stopped() {
+                break;
+            }
+
+            self.flush_overflow()?;
+            if self.aggressive_poll {
+                self.submit_pending()?;
+                // Parked thread has nothing else to run, it blocks until a completion is posted.
+                if parked {
+                    cq.sync();
+                    if cq.is_empty() {
+                        self.submit_and_wait()?;
+                    }
+                }
+            } else {
+                self.submit_and_wait()?;
             }
             cq.sync();
+            self.cq_overflow
+                .store(cq.overflow() as u64, Ordering::Relaxed);
             for cqe in cq.by_ref() {
-                if more(cqe.flags()) {
-                    self.cqe_completion_multi(&cqe)?;
-                } else {
-                    self.cqe_completion_single(&cqe)?;
-                }
+                self.cqe_completion(&cqe, &mut events);
                 acc += 1;
 
                 if !sock_nonempty(cqe.flags()) || !more(cqe.flags()) {
                     break 'sock;
                 }
             }
+            // Parked thread goes back to check whether its own waker fired.
+            if parked && acc > 0 {
+                break;
+            }
         }
+        drop(cq);
+        events.into_iter().for_each(|event| self.emit(event));
 
         Ok(acc)
     }
 
-    fn cqe_completion_multi(&self, cqe: &CQEntry) -> io::Result<()> {
+    /// Stops accepting operations, and waits until the in-flight ones complete or get cancelled.
+    ///
+    /// Threads that wait for completions are released once nothing is in flight.
+    pub(crate) fn shutdown(&self, mode: ShutdownMode) -> io::Result<()> {
+        self.state.drain();
+
+        // Without a deadline, operations are drained until they complete.
+        let deadline = match mode {
+            ShutdownMode::Drain(grace) => Instant::now().checked_add(grace),
+            ShutdownMode::Cancel => Some(Instant::now()),
+        };
+
+        let mut cancelled = false;
+        while !self.submitters.lock().is_empty() {
+            if !cancelled && deadline.is_some_and(|d| Instant::now() >= d) {
+                self.cancel_all();
+                cancelled = true;
+            }
+            self.reap()?;
+        }
+
+        self.state.stop();
+        // Completes the wait of a thread that is blocked on the ring.
+        let nop = OP::Nop::new().build().user_data(INTERNAL_USER_DATA);
+        self.enqueue(&[nop])?;
+        self.sbmt.submit()?;
+
+        Ok(())
+    }
+
+    pub(crate) fn is_shutdown(&self) -> bool {
+        self.state.is_stopped()
+    }
+
+    pub(crate) fn stats(&self) -> ProactorStats {
+        ProactorStats {
+            sqes_submitted: self.sqes_submitted.load(Ordering::Relaxed),
+            cqes_reaped: self.cqes_reaped.load(Ordering::Relaxed),
+            inflight: self.submitters.lock().len() as u64,
+            sq_full_events: self.sq_full_events.load(Ordering::Relaxed),
+            cq_overflow: self.cq_overflow.load(Ordering::Relaxed),
+            wakeups: self.wakeups.load(Ordering::Relaxed),
+            ..ProactorStats::default()
+        }
+    }
+
+    pub(crate) fn inflight(&self) -> Vec<InflightOperation> {
+        let now = Instant::now();
+        let mut ops: Vec<InflightOperation> = self
+            .submitters
+            .lock()
+            .iter()
+            .map(|(id, op)| InflightOperation {
+                id: *id,
+                opcode: Some(op.opcode),
+                class: op_class(op.opcode),
+                fd: op.fd,
+                events: 0,
+                cancelled: op.cancelled,
+                submitted_at: op.submitted_at,
+                age: now.saturating_duration_since(op.submitted_at),
+            })
+            .collect();
+        ops.sort_by_key(|op| (op.submitted_at, op.id));
+        ops
+    }
+
+    pub(crate) fn latency(&self, class: OpClass) -> LatencyHistogram {
+        self.latencies.histogram(class)
+    }
+
+    /// Requests cancellation of every in-flight operation, they complete with `ECANCELED`.
+    fn cancel_all(&self) {
+        let events: Vec<OpEvent> = self
+            .submitters
+            .lock()
+            .iter()
+            .map(|(id, op)| op.event(OpEventKind::Cancel, *id))
+            .collect();
+        for event in events {
+            let sqe = OP::AsyncCancel::new(event.user_data)
+                .build()
+                .user_data(INTERNAL_USER_DATA);
+            let _ = self.submit_entries(&[sqe]);
+            self.emit(event);
+        }
+    }
+
+    /// Calls the event hook, if there is one, without any of the locks held.
+    fn emit(&self, event: OpEvent) {
+        if let Some(hook) = &self.hook {
+            hook.on_event(&event);
+        }
+    }
+
+    /// Submits the queued entries and dispatches the completions that arrive within a tick.
+    ///
+    /// If another thread is waiting on the ring, completions are left to it.
+    fn reap(&self) -> io::Result<()> {
+        self.flush_overflow()?;
+        let Some(mut cq) = self.cq.try_lock() else {
+            self.submit_pending()?;
+            std::thread::sleep(DRIVER_TICK);
+            return Ok(());
+        };
+
+        self.pending.store(0, Ordering::Release);
+        let ts = Timespec::from(DRIVER_TICK);
+        let args = SubmitArgs::new().timespec(&ts);
+        match self.sbmt.submit_with_args(1, &args) {
+            Ok(_) | Err(rustix::io::Errno::TIME) | Err(rustix::io::Errno::BUSY) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let mut events = Vec::new();
+        cq.sync();
+        for cqe in cq.by_ref() {
+            self.cqe_completion(&cqe, &mut events);
+        }
+        drop(cq);
+        events.into_iter().for_each(|event| self.emit(event));
+
+        Ok(())
+    }
+
+    /// Dispatches the completion, its event is collected to be emitted once the completion queue
+    /// is unlocked.
+    fn cqe_completion(&self, cqe: &CQEntry, events: &mut Vec<OpEvent>) {
+        let outer = DISPATCHING.with(|d| d.replace(self as *const _ as usize));
+        let event = if more(cqe.flags()) {
+            self.cqe_completion_multi(cqe)
+        } else {
+            self.cqe_completion_single(cqe)
+        };
+        DISPATCHING.with(|d| d.set(outer));
+        if let (Some(event), Some(_)) = (event, &self.hook) {
+            events.push(event);
+        }
+    }
+
+    /// Whether the current thread dispatches completions of this proactor.
+    fn is_dispatching(&self) -> bool {
+        DISPATCHING.with(|d| d.get() == self as *const _ as usize)
+    }
+
+    fn cqe_completion_multi(&self, cqe: &CQEntry) -> Option<OpEvent> {
+        self.cqes_reaped.fetch_add(1, Ordering::Relaxed);
         let udata = cqe.user_data();
         let res: i32 = cqe.result();
 
-        let sbmts = self.submitters.lock();
-        sbmts.get(&udata).map(|s| s.send(res));
+        let mut sbmts = self.submitters.lock();
+        let completion = sbmts.get_mut(&udata).map(|op| {
+            self.complete(op, res, cqe.flags());
+            // Result of a zero-copy send, which is followed by the notification. Latency is the
+            // one of the send, rather than of the kernel releasing the buffer.
+            if let (Some(class), true, false) =
+                (op_class(op.opcode), zero_copy(op.opcode), op.reposted)
+            {
+                self.latencies.record(class, op.submitted_at.elapsed());
+            }
+            op.reposted = true;
+            OpEvent::completion(op.opcode, op_class(op.opcode), op.fd, udata, res)
+        });
+        drop(sbmts);
         // if atomics are going to be wrapped, channel will be reinserted.
         // which is ok.
 
-        Ok(())
+        completion
     }
 
-    fn cqe_completion_single(&self, cqe: &CQEntry) -> io::Result<()> {
+    fn cqe_completion_single(&self, cqe: &CQEntry) -> Option<OpEvent> {
+        self.cqes_reaped.fetch_add(1, Ordering::Relaxed);
         let udata = cqe.user_data();
         let res: i32 = cqe.result();
 
         let mut sbmts = self.submitters.lock();
-        let x = sbmts.remove(&udata);
-        x.map(|s| s.send(res));
+        let op = sbmts.remove(&udata)?;
 
-        Ok(())
+        self.complete(&op, res, cqe.flags());
+        let class = op_class(op.opcode);
+        if op.cancelled {
+            self.acknowledge_cancel(udata);
+        } else if let (Some(class), false) = (class, op.reposted) {
+            self.latencies.record(class, op.submitted_at.elapsed());
+        }
+        drop(sbmts);
+
+        // Notification of a zero-copy send isn't a result, the result is emitted before it.
+        (!cqe.flags().contains(CQFlags::NOTIF))
+            .then(|| OpEvent::completion(op.opcode, class, op.fd, udata, res))
     }
 }
 
+///
+/// Completion notification of a submitted operation.
+///
+/// Polling never blocks the executor thread: if the completion hasn't arrived yet, the task's waker
+/// is stored next to the in-flight entry and woken from the completion dispatch.
+///
+/// Once all clones are dropped, the operation is cancelled if it is still in flight.
 #[derive(Clone)]
 pub(crate) struct CompletionChan {
-    rx: Receiver<i32>,
+    rx: Receiver<(i32, CQFlags)>,
+    waker: Arc<AtomicWaker>,
+    /// Whether the operation is linked with a timeout.
+    timed: bool,
+    guard: Arc<InflightGuard>,
 }
 
 impl CompletionChan {
-    pub fn get_rx(&self) -> Receiver<i32> {
+    pub fn get_rx(&self) -> Receiver<(i32, CQFlags)> {
         self.rx.clone()
     }
-}
 
-impl CompletionChan {
-    unsafe_pinned!(rx: Receiver<i32>);
+    /// Polls the next completion along with its flags, like the buffer that the kernel selected.
+    pub(crate) fn poll_completion(&mut self, cx: &mut Context) -> Poll<io::Result<(i32, CQFlags)>> {
+        if let Poll::Ready(res) = self.try_complete() {
+            return Poll::Ready(res);
+        }
+
+        // Register before checking again, completion might have arrived in between.
+        self.waker.register(cx.waker());
+        self.try_complete()
+    }
+
+    /// Polls the next completion as the kernel posted it, without turning the result into an error.
+    ///
+    /// [None] once the operation is retired and all of its completions are received.
+    pub(crate) fn poll_cqe(&mut self, cx: &mut Context) -> Poll<Option<(i32, CQFlags)>> {
+        let try_recv = |rx: &Receiver<(i32, CQFlags)>| match rx.try_recv() {
+            Ok(cqe) => Poll::Ready(Some(cqe)),
+            Err(TryRecvError::Empty) => Poll::Pending,
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+        };
+        if let Poll::Ready(cqe) = try_recv(&self.rx) {
+            return Poll::Ready(cqe);
+        }
+
+        self.waker.register(cx.waker());
+        try_recv(&self.rx)
+    }
+
+    /// Turns the result of a completion into the result of the operation.
+    pub(crate) fn result(&self, res: i32) -> io::Result<i32> {
+        match res {
+            // Operation is cancelled by its linked timeout, rather than a shutdown.
+            res if self.timed && res == -libc::ECANCELED && !self.shutting_down() => Err(
+                io::Error::new(io::ErrorKind::TimedOut, "operation timed out"),
+            ),
+            res if res < 0 => Err(io::Error::from_raw_os_error(-res)),
+            res => Ok(res),
+        }
+    }
+
+    fn shutting_down(&self) -> bool {
+        !self.guard.proactor.inner().uring().state.is_running()
+    }
+
+    fn try_complete(&self) -> Poll<io::Result<(i32, CQFlags)>> {
+        match self.rx.try_recv() {
+            Ok((res, flags)) => Poll::Ready(self.result(res).map(|res| (res, flags))),
+            Err(TryRecvError::Empty) => Poll::Pending,
+            Err(TryRecvError::Disconnected) => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "nuclei: operation is dropped before it completes",
+            ))),
+        }
+    }
 }
 
 impl Future for CompletionChan {
     type Output = io::Result<i32>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        let this = self.rx();
-        Poll::Ready(
-            this.recv()
-                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "sender has been cancelled")),
-        )
+        self.get_mut().poll_completion(cx).map_ok(|(res, _)| res)
+    }
+}
+
+impl fmt::Debug for SysProactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SysProactor")
+            .field("backend", &self.backend())
+            .field("inflight", &self.inflight())
+            .finish()
+    }
+}
+
+impl Drop for SysProactor {
+    fn drop(&mut self) {
+        // Kernel might still access the resources of the operations that are not acknowledged.
+        let _ = self.shutdown(ShutdownMode::Cancel);
+        if self.buf_ring.get().is_some() {
+            let _ = self.sbmt.unregister_buf_ring(RECV_BGID);
+        }
+    }
+}
+
+/// Cancels the operation when the last [CompletionChan] of it is dropped.
+struct InflightGuard {
+    /// Proactor the operation is submitted to.
+    proactor: Proactor,
+    id: u64,
+    resources: Cancellation,
+    /// Whether the operation might point at memory that it doesn't own.
+    borrowed: bool,
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        let uring = self.proactor.inner().uring();
+        if self.borrowed {
+            uring.cancel_io_sync(self.id);
+        } else {
+            let resources = mem::replace(&mut self.resources, Cancellation::null());
+            uring.cancel_io(self.id, resources);
+        }
     }
 }
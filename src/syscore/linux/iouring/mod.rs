@@ -1,12 +1,29 @@
+/// Builds the submission on the descriptor, or on its slot when it is a direct descriptor,
+/// see [Handle::register_direct](crate::Handle::register_direct).
+macro_rules! on_fd {
+    ($fd:expr, |$target:ident| $build:expr) => {{
+        let fd: std::os::unix::io::RawFd = $fd;
+        match $crate::syscore::linux::iouring::sys_proactor().direct_slot(fd) {
+            Some(slot) => {
+                let $target = rustix_uring::types::Fixed(slot);
+                $build
+            }
+            None => {
+                let $target = rustix_uring::types::Fd(fd);
+                $build
+            }
+        }
+    }};
+}
+
 pub(crate) mod fs;
 mod iouring;
-mod net;
-mod nethandle;
+pub(crate) mod net;
 mod processor;
+mod timer;
 
 pub(crate) use fs::*;
 pub(crate) use iouring::*;
 
 pub(crate) use processor::*;
-
-pub const BACKEND: crate::sys::IoBackend = crate::sys::IoBackend::IoUring;
+pub(crate) use timer::*;
@@ -0,0 +1,137 @@
+use std::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
+use std::io;
+use std::ptr::NonNull;
+use std::slice;
+use std::sync::atomic::{AtomicU16, AtomicUsize, Ordering};
+use std::task::Waker;
+
+use lever::sync::prelude::*;
+use rustix_uring::types::BufRingEntry;
+
+/// Buffer group of the receive buffer ring, it is the only buffer group of a proactor.
+pub(crate) const RECV_BGID: u16 = 0;
+
+/// Most entries that the kernel accepts in a buffer ring.
+const MAX_ENTRIES: u32 = 1 << 15;
+
+const PAGE_SIZE: usize = 4096;
+
+///
+/// Provided buffer ring (`IORING_REGISTER_PBUF_RING`) that the kernel picks receive buffers from.
+///
+/// Buffers are taken by the kernel when data arrives, and are put back to the ring when the chunk
+/// they are handed out with is dropped. So idle sockets don't hold a buffer.
+pub(crate) struct BufRing {
+    ring: NonNull<BufRingEntry>,
+    ring_layout: Layout,
+    bufs: NonNull<u8>,
+    bufs_layout: Layout,
+    buf_size: usize,
+    entries: u16,
+    /// Tail of the ring, the lock serializes putting buffers back.
+    tail: TTas<u16>,
+    /// Buffers that the kernel has taken and that aren't put back yet.
+    taken: AtomicUsize,
+    /// Tasks whose multishot receive ran out of buffers.
+    waiters: TTas<Vec<Waker>>,
+}
+
+// Ring entries are written behind the tail lock, buffers are accessed through their single chunk.
+unsafe impl Send for BufRing {}
+unsafe impl Sync for BufRing {}
+
+impl BufRing {
+    pub(crate) fn new(entries: u32, buf_size: u32) -> io::Result<BufRing> {
+        if !entries.is_power_of_two() || entries > MAX_ENTRIES || buf_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "nuclei: receive buffer ring needs a power of two entries up to 32768 and non-empty buffers",
+            ));
+        }
+
+        let alloc = |size: usize| {
+            let layout = Layout::from_size_align(size, PAGE_SIZE).unwrap();
+            let data = NonNull::new(unsafe { alloc_zeroed(layout) })
+                .unwrap_or_else(|| handle_alloc_error(layout));
+            (data, layout)
+        };
+        let (ring, ring_layout) = alloc(entries as usize * std::mem::size_of::<BufRingEntry>());
+        let (bufs, bufs_layout) = alloc(entries as usize * buf_size as usize);
+
+        let buf_ring = BufRing {
+            ring: ring.cast(),
+            ring_layout,
+            bufs,
+            bufs_layout,
+            buf_size: buf_size as usize,
+            entries: entries as u16,
+            tail: TTas::new(0),
+            taken: AtomicUsize::new(entries as usize),
+            waiters: TTas::new(Vec::new()),
+        };
+        (0..entries as u16).for_each(|bid| buf_ring.recycle(bid));
+
+        Ok(buf_ring)
+    }
+
+    pub(crate) fn ring_addr(&self) -> u64 {
+        self.ring.as_ptr() as u64
+    }
+
+    pub(crate) fn entries(&self) -> u16 {
+        self.entries
+    }
+
+    /// Data that the kernel received into the buffer.
+    pub(crate) fn buf(&self, bid: u16, len: usize) -> &[u8] {
+        let len = len.min(self.buf_size);
+        unsafe { slice::from_raw_parts(self.bufs.as_ptr().add(bid as usize * self.buf_size), len) }
+    }
+
+    /// Accounts a buffer that the kernel has taken, as its completion is dispatched.
+    pub(crate) fn selected(&self) {
+        self.taken.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Puts the buffer back to the ring, and wakes the receives that ran out of buffers.
+    pub(crate) fn recycle(&self, bid: u16) {
+        let mut tail = self.tail.lock();
+        let mask = self.entries - 1;
+        let entry = unsafe { &mut *self.ring.as_ptr().add((*tail & mask) as usize) };
+        entry.set_addr(unsafe { self.bufs.as_ptr().add(bid as usize * self.buf_size) } as u64);
+        entry.set_len(self.buf_size as u32);
+        entry.set_bid(bid);
+
+        *tail = tail.wrapping_add(1);
+        // Kernel reads the tail from the first entry, it is published after the entry is written.
+        let shared = unsafe { &*(BufRingEntry::tail(self.ring.as_ptr()) as *const AtomicU16) };
+        shared.store(*tail, Ordering::Release);
+        drop(tail);
+
+        self.taken.fetch_sub(1, Ordering::AcqRel);
+        let waiters = std::mem::take(&mut *self.waiters.lock());
+        waiters.into_iter().for_each(Waker::wake);
+    }
+
+    /// Registers the waker to be woken when a buffer is put back, unless the ring has buffers.
+    ///
+    /// Returns whether the task should wait.
+    pub(crate) fn wait_for_buffer(&self, waker: &Waker) -> bool {
+        let mut waiters = self.waiters.lock();
+        if self.taken.load(Ordering::Acquire) < self.entries as usize {
+            return false;
+        }
+
+        waiters.push(waker.clone());
+        true
+    }
+}
+
+impl Drop for BufRing {
+    fn drop(&mut self) {
+        unsafe {
+            dealloc(self.ring.as_ptr().cast(), self.ring_layout);
+            dealloc(self.bufs.as_ptr(), self.bufs_layout);
+        }
+    }
+}
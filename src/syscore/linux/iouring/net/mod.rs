@@ -1 +1,2 @@
+pub(crate) mod buf_ring;
 pub(crate) mod multishot;
@@ -1,55 +1,406 @@
-use crate::syscore::CompletionChan;
-use crate::{Handle, Proactor};
-use crossbeam_channel::RecvError;
+use super::buf_ring::{BufRing, RECV_BGID};
+use crate::syscore::linux::iouring::{sys_proactor, CompletionChan};
+use crate::syscore::Processor;
+use crate::{Direct, Handle, Proactor};
 use futures::Stream;
 use pin_project_lite::pin_project;
 use rustix::io_uring::SocketFlags;
-use rustix_uring::{opcode as OP, types::Fd};
+use rustix_uring::cqueue::{buffer_select, more, Flags as CQFlags};
+use rustix_uring::opcode as OP;
+use std::future::Future;
 use std::io;
-use std::net::TcpStream;
+use std::mem::ManuallyDrop;
+use std::net::{TcpListener, TcpStream};
+use std::ops::Deref;
 use std::os::fd::{AsRawFd, FromRawFd, RawFd};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+type Accept = Pin<Box<dyn Future<Output = io::Result<Handle<TcpStream>>> + Send>>;
+
+///
+/// Where the accepted streams come from.
+enum Source {
+    /// Completions of the multishot accept.
+    Multishot(CompletionChan),
+    /// Single-shot accepts issued one after another, where multishot accept isn't supported.
+    SingleShot(Accept),
+}
+
 pin_project! {
     ///
     /// TcpStream generator that is fed by multishot accept with multiple CQEs.
-    #[derive(Clone)]
+    ///
+    /// On kernels without multishot accept and on epoll, it is fed by single-shot accepts.
     pub struct TcpStreamGenerator {
         listener: RawFd,
-        rx: CompletionChan
+        proactor: Proactor,
+        source: Source,
     }
 }
 
 impl TcpStreamGenerator {
     pub fn new<T: AsRawFd>(listener: &T) -> io::Result<Self> {
-        let sqe = OP::AcceptMulti::new(Fd(listener.as_raw_fd()))
-            .flags(SocketFlags::NONBLOCK)
-            .build();
+        let listener = listener.as_raw_fd();
+        let proactor = Proactor::current();
 
-        let rx = Proactor::get().inner().register_io(sqe)?;
+        let source = if proactor.capabilities().accept_multi {
+            let sqe = on_fd!(listener, |target| {
+                OP::AcceptMulti::new(target)
+                    .flags(SocketFlags::NONBLOCK)
+                    .build()
+            });
+            Source::Multishot(sys_proactor().register_io(sqe)?)
+        } else {
+            Source::SingleShot(accept(proactor.clone(), listener))
+        };
 
         Ok(Self {
-            listener: listener.as_raw_fd(),
-            rx,
+            listener,
+            proactor,
+            source,
         })
     }
 }
 
+/// Accepts a single stream on the listener, which stays owned by its handle.
+fn accept(proactor: Proactor, listener: RawFd) -> Accept {
+    Box::pin(async move {
+        let listener = ManuallyDrop::new(unsafe { TcpListener::from_raw_fd(listener) });
+        let accept = Processor::processor_accept_tcp_listener(&*listener, None);
+        let (stream, _) = proactor.route(accept).await?;
+        Ok(stream)
+    })
+}
+
+impl Clone for TcpStreamGenerator {
+    fn clone(&self) -> Self {
+        let source = match &self.source {
+            Source::Multishot(rx) => Source::Multishot(rx.clone()),
+            Source::SingleShot(_) => {
+                Source::SingleShot(accept(self.proactor.clone(), self.listener))
+            }
+        };
+
+        Self {
+            listener: self.listener,
+            proactor: self.proactor.clone(),
+            source,
+        }
+    }
+}
+
 impl Stream for TcpStreamGenerator {
     type Item = Handle<TcpStream>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.project();
 
-        match this.rx.get_rx().recv() {
-            Ok(sfd) => {
-                let stream = unsafe { TcpStream::from_raw_fd(sfd) };
-                let hs = Handle::new(stream).unwrap();
-                Poll::Ready(Some(hs))
+        match this.source {
+            Source::Multishot(rx) => match futures::ready!(Pin::new(rx).poll(cx)) {
+                Ok(sfd) => {
+                    // Accepted streams are owned by the proactor of the listener.
+                    let _guard = this.proactor.enter();
+                    let stream = unsafe { TcpStream::from_raw_fd(sfd) };
+                    let hs = Handle::new(stream).unwrap();
+                    Poll::Ready(Some(hs))
+                }
+                // Multishot accept is terminated and all accepted streams are drained.
+                Err(_) => Poll::Ready(None),
+            },
+            Source::SingleShot(pending) => match futures::ready!(pending.as_mut().poll(cx)) {
+                Ok(hs) => {
+                    *pending = accept(this.proactor.clone(), *this.listener);
+                    Poll::Ready(Some(hs))
+                }
+                // Same as multishot accept, the generator ends with the first failure.
+                Err(_) => Poll::Ready(None),
+            },
+        }
+    }
+}
+
+///
+/// Generator of the streams that multishot accept installs straight into the file table of the
+/// ring, see [Handle::accept_multi_direct].
+pub struct DirectStreamGenerator {
+    proactor: Proactor,
+    rx: CompletionChan,
+}
+
+impl DirectStreamGenerator {
+    pub(crate) fn new<T: AsRawFd>(listener: &T) -> io::Result<Self> {
+        let proactor = Proactor::current();
+        let capabilities = proactor.capabilities();
+        if !(capabilities.direct_files && capabilities.accept_multi) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "nuclei: direct multishot accept requires io_uring with allocated file slots (5.19+)",
+            ));
+        }
+        sys_proactor().file_table()?;
+
+        let sqe = on_fd!(listener.as_raw_fd(), |target| {
+            OP::AcceptMulti::new(target)
+                .allocate_file_index(true)
+                .build()
+        });
+        let rx = sys_proactor().register_io(sqe)?;
+
+        Ok(Self { proactor, rx })
+    }
+}
+
+impl Stream for DirectStreamGenerator {
+    type Item = Handle<Direct>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match futures::ready!(Pin::new(&mut self.rx).poll(cx)) {
+            Ok(slot) => Poll::Ready(Some(Handle::direct(Direct {
+                slot: slot as _,
+                proactor: self.proactor.clone(),
+            }))),
+            // Multishot accept is terminated and all accepted streams are drained.
+            Err(_) => Poll::Ready(None),
+        }
+    }
+}
+
+/// Size of the chunks that single-shot receives read into.
+const RECV_CHUNK_SIZE: usize = 4096;
+
+type Recv = Pin<Box<dyn Future<Output = io::Result<Vec<u8>>> + Send>>;
+
+///
+/// Buffer of the receive buffer ring, or the chunk that a single-shot receive read into.
+enum Chunk {
+    Ring {
+        proactor: Proactor,
+        bid: u16,
+        len: usize,
+    },
+    Owned(Vec<u8>),
+}
+
+///
+/// Chunk of bytes received by [RecvStream].
+///
+/// Chunks of multishot receives are buffers of the provided buffer ring of the proactor,
+/// the buffer goes back to the ring when the chunk is dropped.
+pub struct RecvChunk {
+    chunk: Chunk,
+}
+
+impl Deref for RecvChunk {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.chunk {
+            Chunk::Ring { proactor, bid, len } => buf_ring(proactor).buf(*bid, *len),
+            Chunk::Owned(data) => data,
+        }
+    }
+}
+
+impl Drop for RecvChunk {
+    fn drop(&mut self) {
+        if let Chunk::Ring { proactor, bid, .. } = &self.chunk {
+            buf_ring(proactor).recycle(*bid);
+        }
+    }
+}
+
+impl std::fmt::Debug for RecvChunk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecvChunk")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Buffer ring of the proactor, which is registered before any of its buffers are handed out.
+fn buf_ring(proactor: &Proactor) -> &BufRing {
+    proactor
+        .inner()
+        .uring()
+        .recv_buf_ring()
+        .expect("nuclei: receive buffer ring is registered before its buffers are received")
+}
+
+///
+/// Where the received chunks come from.
+enum RecvSource {
+    /// Completions of the multishot receive, [None] when it has to be armed again.
+    Multishot(Option<CompletionChan>),
+    /// Single-shot receives issued one after another, where multishot receive isn't supported.
+    SingleShot(Recv),
+    /// Stream is ended by the end of the stream or by a failure.
+    Done,
+}
+
+///
+/// Stream of the chunks received on a socket, fed by multishot receive with multiple CQEs.
+///
+/// The kernel picks the buffers from the provided buffer ring of the proactor, see
+/// [IoUringConfiguration](crate::config::IoUringConfiguration), so idle sockets don't hold any
+/// buffer. When the ring runs out of buffers, the receive is armed again once a chunk is dropped.
+///
+/// On kernels without provided buffer rings or multishot receive, and on epoll, it is fed by
+/// single-shot receives. The stream ends when the peer closes the connection, or after the first
+/// failure.
+pub struct RecvStream {
+    socket: RawFd,
+    proactor: Proactor,
+    source: RecvSource,
+}
+
+impl RecvStream {
+    pub fn new<T: AsRawFd>(socket: &T) -> io::Result<Self> {
+        let socket = socket.as_raw_fd();
+        let proactor = Proactor::current();
+
+        let capabilities = proactor.capabilities();
+        let source = if capabilities.buf_ring && capabilities.recv_multi {
+            proactor.inner().uring().recv_buf_ring()?;
+            RecvSource::Multishot(Some(recv_multi(socket)?))
+        } else {
+            RecvSource::SingleShot(recv(proactor.clone(), socket))
+        };
+
+        Ok(Self {
+            socket,
+            proactor,
+            source,
+        })
+    }
+
+    /// Polls the multishot receive, arming it again when the kernel terminates it.
+    fn poll_multishot(&mut self, cx: &mut Context<'_>) -> Poll<Option<io::Result<RecvChunk>>> {
+        let RecvSource::Multishot(armed) = &mut self.source else {
+            unreachable!("nuclei: multishot receive is polled on another source");
+        };
+        let buf_ring = buf_ring(&self.proactor);
+
+        loop {
+            if armed.is_none() {
+                let _guard = self.proactor.enter();
+                match recv_multi(self.socket) {
+                    Ok(chan) => *armed = Some(chan),
+                    Err(e) => {
+                        self.source = RecvSource::Done;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                }
             }
-            Err(e) if e == RecvError => Poll::Ready(None),
-            _ => Poll::Pending,
+            let chan = armed.as_mut().unwrap();
+
+            match futures::ready!(chan.poll_completion(cx)) {
+                // Peer closed the connection.
+                Ok((0, flags)) => {
+                    recycle(buf_ring, flags);
+                    break;
+                }
+                Ok((len, flags)) => {
+                    let Some(bid) = buffer_select(flags) else {
+                        break;
+                    };
+                    // Kernel terminated the multishot receive, it is armed on the next poll.
+                    if !more(flags) {
+                        *armed = None;
+                    }
+
+                    return Poll::Ready(Some(Ok(RecvChunk {
+                        chunk: Chunk::Ring {
+                            proactor: self.proactor.clone(),
+                            bid,
+                            len: len as usize,
+                        },
+                    })));
+                }
+                // Ring ran out of buffers, receive again once a chunk is dropped.
+                Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+                    *armed = None;
+                    if buf_ring.wait_for_buffer(cx.waker()) {
+                        return Poll::Pending;
+                    }
+                }
+                Err(e) => {
+                    self.source = RecvSource::Done;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+
+        self.source = RecvSource::Done;
+        Poll::Ready(None)
+    }
+}
+
+/// Arms a multishot receive on the socket, with buffers of the receive buffer ring.
+fn recv_multi(socket: RawFd) -> io::Result<CompletionChan> {
+    let sqe = on_fd!(socket, |target| {
+        OP::RecvMulti::new(target, RECV_BGID).build()
+    });
+    sys_proactor().register_io(sqe)
+}
+
+/// Receives a single chunk on the socket, which stays owned by its handle.
+fn recv(proactor: Proactor, socket: RawFd) -> Recv {
+    Box::pin(async move {
+        let mut data = vec![0; RECV_CHUNK_SIZE];
+        let len = proactor
+            .route(Processor::processor_recv(&socket, &mut data, None))
+            .await?;
+        data.truncate(len);
+        Ok(data)
+    })
+}
+
+/// Puts the buffer that the kernel selected for the completion back to the ring.
+fn recycle(buf_ring: &BufRing, flags: CQFlags) {
+    if let Some(bid) = buffer_select(flags) {
+        buf_ring.recycle(bid);
+    }
+}
+
+impl Stream for RecvStream {
+    type Item = io::Result<RecvChunk>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        match &mut this.source {
+            RecvSource::Multishot(_) => this.poll_multishot(cx),
+            RecvSource::SingleShot(pending) => match futures::ready!(pending.as_mut().poll(cx)) {
+                Ok(data) if data.is_empty() => {
+                    this.source = RecvSource::Done;
+                    Poll::Ready(None)
+                }
+                Ok(data) => {
+                    *pending = recv(this.proactor.clone(), this.socket);
+                    Poll::Ready(Some(Ok(RecvChunk {
+                        chunk: Chunk::Owned(data),
+                    })))
+                }
+                Err(e) => {
+                    this.source = RecvSource::Done;
+                    Poll::Ready(Some(Err(e)))
+                }
+            },
+            RecvSource::Done => Poll::Ready(None),
+        }
+    }
+}
+
+impl Drop for RecvStream {
+    fn drop(&mut self) {
+        if let RecvSource::Multishot(Some(chan)) = &self.source {
+            // Receive is cancelled with the channel, buffers that it received are put back.
+            let rx = chan.get_rx();
+            self.source = RecvSource::Done;
+            let buf_ring = buf_ring(&self.proactor);
+            rx.try_iter()
+                .for_each(|(_, flags)| recycle(buf_ring, flags));
         }
     }
 }
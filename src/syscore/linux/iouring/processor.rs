@@ -1,4 +1,4 @@
-use std::future::Future;
+use std::future::{poll_fn, Future};
 
 use std::io;
 use std::net::TcpStream;
@@ -8,17 +8,22 @@ use std::os::unix::io::{AsRawFd, FromRawFd};
 use std::os::unix::net::{SocketAddr as UnixSocketAddr, UnixStream};
 use std::path::Path;
 
-use crate::proactor::Proactor;
-
-use crate::syscore::shim_to_af_unix;
-use crate::Handle;
+use super::fs::cancellation::Cancellation;
+use super::{shim_to_af_unix, sys_proactor, CompletionChan};
+use crate::{FixedBuf, Handle};
 use libc::sockaddr_un;
 use os_socketaddr::OsSocketAddr;
 
-use rustix::io_uring::{msghdr, IoringRecvFlags, RecvFlags, SendFlags, SocketFlags};
+use rustix::io_uring::{
+    msghdr, IoringRecvFlags, RecvFlags, SendFlags, SocketFlags, IORING_FILE_INDEX_ALLOC,
+};
 use rustix::net::{SocketAddrAny, SocketAddrUnix};
 
-use rustix_uring::types::{socklen_t, AtFlags, Mode, OFlags, Statx, StatxFlags};
+use rustix_uring::cqueue::{more, Flags as CQFlags};
+use rustix_uring::squeue::Entry as SQEntry;
+use rustix_uring::types::{
+    socklen_t, AtFlags, DestinationSlot, Fixed, Mode, OFlags, Statx, StatxFlags,
+};
 use rustix_uring::{opcode as OP, types::Fd};
 use socket2::SockAddr;
 use std::ffi::CString;
@@ -28,6 +33,7 @@ use std::os::fd::OwnedFd;
 use std::os::unix::ffi::OsStrExt;
 use std::os::unix::prelude::RawFd;
 use std::ptr::null_mut;
+use std::time::Duration;
 
 macro_rules! syscall {
     ($fn:ident $args:tt) => {{
@@ -49,14 +55,13 @@ impl Processor {
 
     pub(crate) async fn processor_open_at(path: impl AsRef<Path>) -> io::Result<usize> {
         let path = CString::new(path.as_ref().as_os_str().as_bytes()).expect("invalid path");
-        let path = path.as_ptr();
         let dfd = libc::AT_FDCWD;
-        let sqe = OP::OpenAt::new(Fd(dfd.as_raw_fd()), path)
+        let sqe = OP::OpenAt::new(Fd(dfd.as_raw_fd()), path.as_ptr())
             .flags(OFlags::CLOEXEC | OFlags::RDONLY)
             .mode(Mode::from(0o666))
             .build();
 
-        let cc = Proactor::get().inner().register_io(sqe)?;
+        let cc = sys_proactor().register_io_with(sqe, Cancellation::boxed(Box::new(path)), None)?;
 
         let x = cc.await? as _;
 
@@ -67,12 +72,15 @@ impl Processor {
         io: &RawFd,
         buf: &mut [u8],
         offset: usize,
+        timeout: Option<Duration>,
     ) -> io::Result<usize> {
-        let sqe = OP::Read::new(Fd(*io), buf.as_mut_ptr(), buf.len() as _)
-            .offset(offset as _)
-            .build();
+        let sqe = on_fd!(*io, |target| {
+            OP::Read::new(target, buf.as_mut_ptr(), buf.len() as _)
+                .offset(offset as _)
+                .build()
+        });
 
-        let cc = Proactor::get().inner().register_io(sqe)?;
+        let cc = sys_proactor().register_io_with(sqe, Cancellation::null(), timeout)?;
 
         Ok(cc.await? as _)
     }
@@ -82,23 +90,155 @@ impl Processor {
         buf: &[u8],
         offset: usize,
     ) -> io::Result<usize> {
-        let sqe = OP::Write::new(Fd(*io), buf.as_ptr(), buf.len() as _)
-            .offset(offset as _)
-            .build();
+        let sqe = on_fd!(*io, |target| {
+            OP::Write::new(target, buf.as_ptr(), buf.len() as _)
+                .offset(offset as _)
+                .build()
+        });
 
-        let cc = Proactor::get().inner().register_io(sqe)?;
+        let cc = sys_proactor().register_io(sqe)?;
 
         Ok(cc.await? as _)
     }
 
+    pub(crate) async fn processor_read_fixed(
+        io: &RawFd,
+        mut buf: FixedBuf,
+        offset: usize,
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, FixedBuf)> {
+        let sqe = on_fd!(*io, |target| {
+            OP::ReadFixed::new(target, buf.as_mut_ptr(), buf.capacity() as _, buf.index())
+                .offset(offset as _)
+                .build()
+        });
+
+        let (n, mut buf) = Self::submit_fixed(sqe, buf, timeout).await?;
+        buf.set_len(n);
+
+        Ok((n, buf))
+    }
+
+    pub(crate) async fn processor_write_fixed(
+        io: &RawFd,
+        buf: FixedBuf,
+        offset: usize,
+    ) -> io::Result<(usize, FixedBuf)> {
+        let sqe = on_fd!(*io, |target| {
+            OP::WriteFixed::new(target, buf.as_ptr(), buf.len() as _, buf.index())
+                .offset(offset as _)
+                .build()
+        });
+
+        Self::submit_fixed(sqe, buf, None).await
+    }
+
+    /// Submits the operation on the fixed buffer, which is owned by the completion meanwhile.
+    ///
+    /// So if the future is dropped, the buffer isn't leased again before the kernel is done with it.
+    async fn submit_fixed(
+        sqe: SQEntry,
+        buf: FixedBuf,
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, FixedBuf)> {
+        let held = Box::into_raw(Box::new(Some(buf)));
+
+        let resources = Cancellation::boxed(unsafe { Box::from_raw(held) });
+        let mut cc = sys_proactor().register_io_with(sqe, resources, timeout)?;
+        let res = (&mut cc).await;
+        // Completion is awaited, so the buffer is taken back before the completion is dropped.
+        let buf = unsafe { (*held).take().unwrap() };
+        drop(cc);
+
+        Ok((res? as _, buf))
+    }
+
     pub(crate) async fn processor_close_file(io: &RawFd) -> io::Result<usize> {
         let sqe = OP::Close::new(Fd(*io)).build();
 
-        let cc = Proactor::get().inner().register_io(sqe)?;
+        let cc = sys_proactor().register_io(sqe)?;
+
+        Ok(cc.await? as _)
+    }
+
+    pub(crate) async fn processor_register_direct(io: &RawFd) -> io::Result<u32> {
+        Self::check_direct()?;
+
+        let fds = Box::into_raw(Box::new([*io]));
+        let sqe = OP::FilesUpdate::new(fds as *const _, 1)
+            .offset(IORING_FILE_INDEX_ALLOC)
+            .build();
+
+        let resources = Cancellation::boxed(unsafe { Box::from_raw(fds) });
+        let mut cc = sys_proactor().register_io_with(sqe, resources, None)?;
+        (&mut cc).await?;
+        // Kernel writes the allocated slot in place of the descriptor.
+        let slot = unsafe { (*fds)[0] } as u32;
+        drop(cc);
+
+        sys_proactor().file_table()?.lock().insert(*io, slot);
+
+        Ok(slot)
+    }
+
+    /// Opens the file straight into a slot of the file table, it is never installed as a descriptor.
+    pub(crate) async fn processor_open_direct(path: impl AsRef<Path>) -> io::Result<u32> {
+        Self::check_direct()?;
+        let path = CString::new(path.as_ref().as_os_str().as_bytes()).expect("invalid path");
+        let sqe = OP::OpenAt::new(Fd(libc::AT_FDCWD), path.as_ptr())
+            .file_index(Some(DestinationSlot::auto_target()))
+            // Slots aren't inherited on exec, the kernel rejects `O_CLOEXEC` for them.
+            .flags(OFlags::RDWR)
+            .build();
+
+        let cc = sys_proactor().register_io_with(sqe, Cancellation::boxed(Box::new(path)), None)?;
+
+        Ok(cc.await? as _)
+    }
+
+    pub(crate) async fn processor_read_direct(
+        slot: u32,
+        buf: &mut [u8],
+        offset: usize,
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        let sqe = OP::Read::new(Fixed(slot), buf.as_mut_ptr(), buf.len() as _)
+            .offset(offset as _)
+            .build();
+
+        let cc = sys_proactor().register_io_with(sqe, Cancellation::null(), timeout)?;
+
+        Ok(cc.await? as _)
+    }
+
+    pub(crate) async fn processor_write_direct(
+        slot: u32,
+        buf: &[u8],
+        offset: usize,
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        let sqe = OP::Write::new(Fixed(slot), buf.as_ptr(), buf.len() as _)
+            .offset(offset as _)
+            .build();
+
+        let cc = sys_proactor().register_io_with(sqe, Cancellation::null(), timeout)?;
 
         Ok(cc.await? as _)
     }
 
+    /// Checks that slots can be allocated, and registers the file table.
+    pub(crate) fn check_direct() -> io::Result<()> {
+        if !sys_proactor().capabilities().direct_files {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "nuclei: direct descriptors require allocated file slots (5.19+)",
+            ));
+        }
+        sys_proactor().file_table()?;
+
+        Ok(())
+    }
+
     pub(crate) async fn processor_file_size(io: &RawFd, statx: *mut Statx) -> io::Result<usize> {
         static EMPTY: libc::c_char = 0;
         let flags = libc::AT_EMPTY_PATH;
@@ -109,7 +249,7 @@ impl Processor {
             .mask(StatxFlags::SIZE)
             .build();
 
-        Proactor::get().inner().register_io(sqe)?.await?;
+        sys_proactor().register_io(sqe)?.await?;
 
         unsafe { Ok((*statx).stx_size as usize) }
     }
@@ -118,11 +258,13 @@ impl Processor {
         io: &RawFd,
         bufs: &mut [IoSliceMut<'_>],
     ) -> io::Result<usize> {
-        let sqe = OP::Readv::new(Fd(*io), bufs as *mut _ as *mut _, bufs.len() as _)
-            .offset(0_u64)
-            .build();
+        let sqe = on_fd!(*io, |target| {
+            OP::Readv::new(target, bufs as *mut _ as *mut _, bufs.len() as _)
+                .offset(0_u64)
+                .build()
+        });
 
-        let cc = Proactor::get().inner().register_io(sqe)?;
+        let cc = sys_proactor().register_io(sqe)?;
 
         Ok(cc.await? as _)
     }
@@ -131,52 +273,318 @@ impl Processor {
         io: &RawFd,
         bufs: &[IoSlice<'_>],
     ) -> io::Result<usize> {
-        let sqe = OP::Writev::new(Fd(*io), bufs as *const _ as *const _, bufs.len() as _)
-            .offset(0_u64)
-            .build();
+        let sqe = on_fd!(*io, |target| {
+            OP::Writev::new(target, bufs as *const _ as *const _, bufs.len() as _)
+                .offset(0_u64)
+                .build()
+        });
 
-        let cc = Proactor::get().inner().register_io(sqe)?;
+        let cc = sys_proactor().register_io(sqe)?;
 
         Ok(cc.await? as _)
     }
 
+    ///////////////////////////////////
+    ///// Splice, Tee
+    ///// One side of the transfer is a pipe
+    ///////////////////////////////////
+
+    pub(crate) async fn processor_splice(
+        fd_in: &RawFd,
+        off_in: Option<u64>,
+        fd_out: &RawFd,
+        len: usize,
+    ) -> io::Result<usize> {
+        if !sys_proactor().capabilities().splice {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "nuclei: kernel doesn't support splice on io_uring",
+            ));
+        }
+
+        // Offset -1 makes the kernel use, and advance, the file position of the input.
+        let off_in = off_in.map_or(-1, |off| off as i64);
+        let len = len.min(u32::MAX as usize) as u32;
+        loop {
+            let sqe = on_fd!(*fd_in, |src| {
+                on_fd!(*fd_out, |dst| {
+                    OP::Splice::new(src, off_in, dst, -1, len).build()
+                })
+            });
+
+            match sys_proactor().register_io(sqe)?.await {
+                Ok(res) => return Ok(res as _),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    Self::splice_ready(*fd_in, *fd_out).await?
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub(crate) async fn processor_tee(
+        fd_in: &RawFd,
+        fd_out: &RawFd,
+        len: usize,
+    ) -> io::Result<usize> {
+        if !sys_proactor().capabilities().tee {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "nuclei: kernel doesn't support tee on io_uring",
+            ));
+        }
+
+        let len = len.min(u32::MAX as usize) as u32;
+        loop {
+            let sqe = on_fd!(*fd_in, |src| {
+                on_fd!(*fd_out, |dst| OP::Tee::new(src, dst, len).build())
+            });
+
+            match sys_proactor().register_io(sqe)?.await {
+                Ok(res) => return Ok(res as _),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    Self::splice_ready(*fd_in, *fd_out).await?
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Waits until the side of the transfer that blocked is ready again.
+    ///
+    /// Kernel doesn't poll the non-blocking descriptors of a splice by itself, it fails with
+    /// `EAGAIN` instead.
+    async fn splice_ready(fd_in: RawFd, fd_out: RawFd) -> io::Result<()> {
+        let mut fds = [
+            libc::pollfd {
+                fd: fd_in,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: fd_out,
+                events: libc::POLLOUT,
+                revents: 0,
+            },
+        ];
+        syscall!(poll(fds.as_mut_ptr(), fds.len() as _, 0))?;
+
+        let (fd, events) = if fds[0].revents == 0 {
+            (fd_in, libc::POLLIN)
+        } else if fds[1].revents == 0 {
+            (fd_out, libc::POLLOUT)
+        } else {
+            // Became ready in the meantime, errors surface on the retry.
+            return Ok(());
+        };
+        let sqe = on_fd!(fd, |target| OP::PollAdd::new(target, events as _).build());
+        sys_proactor().register_io(sqe)?.await?;
+
+        Ok(())
+    }
+
     ///////////////////////////////////
     ///// Send, Recv, Peek
     ///// Commonality of TcpStream, UdpSocket, UnixStream, UnixDatagram
     ///////////////////////////////////
 
-    pub(crate) async fn processor_send<R: AsRawFd>(socket: &R, buf: &[u8]) -> io::Result<usize> {
+    pub(crate) async fn processor_send<R: AsRawFd>(
+        socket: &R,
+        buf: &[u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
         let fd = socket.as_raw_fd() as _;
 
-        let sqe = OP::Send::new(Fd(fd), buf.as_ptr() as _, buf.len() as _)
+        let sqe = on_fd!(fd, |target| {
+            OP::Send::new(target, buf.as_ptr() as _, buf.len() as _)
+                .flags(SendFlags::empty())
+                .build()
+        });
+
+        let res = sys_proactor()
+            .register_io_with(sqe, Cancellation::null(), timeout)?
+            .await?;
+
+        Ok(res as _)
+    }
+
+    pub(crate) async fn processor_recv<R: AsRawFd>(
+        sock: &R,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        Self::recv_with_flags(sock, buf, RecvFlags::empty(), timeout).await
+    }
+
+    pub(crate) async fn processor_peek<R: AsRawFd>(
+        sock: &R,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        Self::recv_with_flags(sock, buf, RecvFlags::PEEK, timeout).await
+    }
+
+    pub(crate) async fn processor_send_fixed<R: AsRawFd>(
+        socket: &R,
+        buf: FixedBuf,
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, FixedBuf)> {
+        let fd = socket.as_raw_fd() as _;
+
+        // Sockets aren't seekable, the offset is ignored.
+        let sqe = on_fd!(fd, |target| {
+            OP::WriteFixed::new(target, buf.as_ptr(), buf.len() as _, buf.index()).build()
+        });
+
+        Self::submit_fixed(sqe, buf, timeout).await
+    }
+
+    pub(crate) async fn processor_recv_fixed<R: AsRawFd>(
+        socket: &R,
+        mut buf: FixedBuf,
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, FixedBuf)> {
+        let fd = socket.as_raw_fd() as _;
+
+        let sqe = on_fd!(fd, |target| {
+            OP::ReadFixed::new(target, buf.as_mut_ptr(), buf.capacity() as _, buf.index()).build()
+        });
+
+        let (n, mut buf) = Self::submit_fixed(sqe, buf, timeout).await?;
+        buf.set_len(n);
+
+        Ok((n, buf))
+    }
+
+    pub(crate) async fn processor_send_direct(
+        slot: u32,
+        buf: &[u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        let sqe = OP::Send::new(Fixed(slot), buf.as_ptr() as _, buf.len() as _)
             .flags(SendFlags::empty())
             .build();
 
-        let res = Proactor::get().inner().register_io(sqe)?.await?;
+        let res = sys_proactor()
+            .register_io_with(sqe, Cancellation::null(), timeout)?
+            .await?;
 
         Ok(res as _)
     }
 
-    pub(crate) async fn processor_recv<R: AsRawFd>(sock: &R, buf: &mut [u8]) -> io::Result<usize> {
-        Self::recv_with_flags(sock, buf, RecvFlags::empty()).await
+    pub(crate) async fn processor_recv_direct(
+        slot: u32,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        let sqe = OP::Recv::new(Fixed(slot), buf.as_mut_ptr(), buf.len() as _)
+            .flags(RecvFlags::empty())
+            .build();
+
+        let res = sys_proactor()
+            .register_io_with(sqe, Cancellation::null(), timeout)?
+            .await?;
+
+        Ok(res as _)
     }
 
-    pub(crate) async fn processor_peek<R: AsRawFd>(sock: &R, buf: &mut [u8]) -> io::Result<usize> {
-        Self::recv_with_flags(sock, buf, RecvFlags::PEEK).await
+    pub(crate) async fn processor_send_zc<R: AsRawFd, B: AsRef<[u8]> + Send + 'static>(
+        socket: &R,
+        buf: B,
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, B)> {
+        if !sys_proactor().capabilities().send_zc {
+            let n = Self::processor_send(socket, buf.as_ref(), timeout).await?;
+            return Ok((n, buf));
+        }
+        let fd = socket.as_raw_fd() as _;
+
+        let held = Box::into_raw(Box::new(Some(buf)));
+        let data = unsafe { (*held).as_ref().unwrap().as_ref() };
+        let sqe = on_fd!(fd, |target| {
+            OP::SendZc::new(target, data.as_ptr(), data.len() as _).build()
+        });
+
+        let resources = Cancellation::boxed(unsafe { Box::from_raw(held) });
+        let mut cc = sys_proactor().register_io_with(sqe, resources, timeout)?;
+        let res = Self::zero_copy_result(&mut cc).await;
+        // Notification is received, so the buffer is taken back before the completion is dropped.
+        let buf = unsafe { (*held).take().unwrap() };
+        drop(cc);
+
+        Ok((res?, buf))
+    }
+
+    pub(crate) async fn processor_send_to_zc<R: AsRawFd, B: AsRef<[u8]> + Send + 'static>(
+        socket: &R,
+        buf: B,
+        addr: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> io::Result<(usize, B)> {
+        if !sys_proactor().capabilities().sendmsg_zc {
+            let n = Self::processor_send_to(socket, buf.as_ref(), addr, timeout).await?;
+            return Ok((n, buf));
+        }
+        let fd = socket.as_raw_fd() as _;
+
+        let held = Box::into_raw(Box::new(Some(buf)));
+        let data = unsafe { (*held).as_ref().unwrap().as_ref() };
+        let mut msg = MsgHdr::new(data.as_ptr() as *mut _, data.len());
+        msg.set_dest(&socket2::SockAddr::from(addr));
+        let sqe = on_fd!(fd, |target| {
+            OP::SendMsgZc::new(target, &msg.hdr as *const _ as *const _).build()
+        });
+
+        let resources = Cancellation::boxed(Box::new((unsafe { Box::from_raw(held) }, msg)));
+        let mut cc = sys_proactor().register_io_with(sqe, resources, timeout)?;
+        let res = Self::zero_copy_result(&mut cc).await;
+        // Notification is received, so the buffer is taken back before the completion is dropped.
+        let buf = unsafe { (*held).take().unwrap() };
+        drop(cc);
+
+        Ok((res?, buf))
+    }
+
+    /// Awaits the result of the zero-copy send, along with the release of its resources.
+    ///
+    /// The result comes first, and when the kernel flags it with more completions, the
+    /// notification that the kernel is done with the buffer follows. If the future is dropped in
+    /// between, the resources are held by the cancellation until the notification.
+    async fn zero_copy_result(cc: &mut CompletionChan) -> io::Result<usize> {
+        let Some((res, flags)) = poll_fn(|cx| cc.poll_cqe(cx)).await else {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "nuclei: zero-copy send is retired without a result",
+            ));
+        };
+        if more(flags) {
+            while let Some((_, flags)) = poll_fn(|cx| cc.poll_cqe(cx)).await {
+                if flags.contains(CQFlags::NOTIF) {
+                    break;
+                }
+            }
+        }
+
+        Ok(cc.result(res)? as _)
     }
 
     async fn recv_with_flags<R: AsRawFd>(
         socket: &R,
         buf: &mut [u8],
         flags: RecvFlags,
+        timeout: Option<Duration>,
     ) -> io::Result<usize> {
         let fd = socket.as_raw_fd() as _;
 
-        let sqe = OP::Recv::new(Fd(fd), buf.as_mut_ptr(), buf.len() as _)
-            .flags(flags)
-            .build();
+        let sqe = on_fd!(fd, |target| {
+            OP::Recv::new(target, buf.as_mut_ptr(), buf.len() as _)
+                .flags(flags)
+                .build()
+        });
 
-        let res = Proactor::get().inner().register_io(sqe)?.await?;
+        let res = sys_proactor()
+            .register_io_with(sqe, Cancellation::null(), timeout)?
+            .await?;
 
         Ok(res as _)
     }
@@ -213,7 +621,10 @@ impl Processor {
         }))
     }
 
-    pub(crate) async fn processor_connect_tcp(addr: SocketAddr) -> io::Result<Handle<TcpStream>> {
+    pub(crate) async fn processor_connect_tcp(
+        addr: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> io::Result<Handle<TcpStream>> {
         let addr = addr.to_string();
         // FIXME: address resolution is always blocking.
         let addr: SocketAddr = addr.to_socket_addrs()?.next().ok_or_else(|| {
@@ -240,7 +651,7 @@ impl Processor {
         stream.set_nodelay(true)?;
         let fd = stream.as_raw_fd() as _;
 
-        let ossa: OsSocketAddr = addr.into();
+        let ossa: Box<OsSocketAddr> = Box::new(addr.into());
         let socklen = ossa.len();
 
         let sqe = OP::Connect::new(
@@ -250,7 +661,9 @@ impl Processor {
         )
         .build();
 
-        Proactor::get().inner().register_io(sqe)?.await?;
+        sys_proactor()
+            .register_io_with(sqe, Cancellation::boxed(ossa), timeout)?
+            .await?;
 
         Handle::new(stream)
     }
@@ -295,19 +708,42 @@ impl Processor {
 
     pub(crate) async fn processor_accept_tcp_listener<R: AsRawFd>(
         listener: &R,
+        timeout: Option<Duration>,
     ) -> io::Result<(Handle<TcpStream>, Option<SocketAddr>)> {
         let fd = listener.as_raw_fd() as _;
 
-        let sqe = OP::Accept::new(Fd(fd), null_mut(), null_mut())
-            .flags(SocketFlags::NONBLOCK)
-            .build();
+        let sqe = on_fd!(fd, |target| {
+            OP::Accept::new(target, null_mut(), null_mut())
+                .flags(SocketFlags::NONBLOCK)
+                .build()
+        });
 
-        let cc = Proactor::get().inner().register_io(sqe)?;
+        let cc = sys_proactor().register_io_with(sqe, Cancellation::null(), timeout)?;
         let stream = unsafe { TcpStream::from_raw_fd(cc.await?) };
 
         Ok((Handle::new(stream).unwrap(), None))
     }
 
+    /// Accepts a stream straight into a slot of the file table, it is never installed as a
+    /// descriptor.
+    pub(crate) async fn processor_accept_direct<R: AsRawFd>(
+        listener: &R,
+        timeout: Option<Duration>,
+    ) -> io::Result<u32> {
+        Self::check_direct()?;
+        let fd = listener.as_raw_fd() as _;
+
+        let sqe = on_fd!(fd, |target| {
+            OP::Accept::new(target, null_mut(), null_mut())
+                .file_index(Some(DestinationSlot::auto_target()))
+                .build()
+        });
+
+        let cc = sys_proactor().register_io_with(sqe, Cancellation::null(), timeout)?;
+
+        Ok(cc.await? as _)
+    }
+
     ///////////////////////////////////
     ///// UdpSocket
     ///////////////////////////////////
@@ -316,31 +752,31 @@ impl Processor {
         socket: &R,
         buf: &[u8],
         addr: SocketAddr,
+        timeout: Option<Duration>,
     ) -> io::Result<usize> {
-        Self::send_to_dest(socket, buf, &socket2::SockAddr::from(addr)).await
+        Self::send_to_dest(socket, buf, &socket2::SockAddr::from(addr), timeout).await
     }
 
     async fn send_to_dest<A: AsRawFd>(
         socket: &A,
         buf: &[u8],
         addr: &socket2::SockAddr,
+        timeout: Option<Duration>,
     ) -> io::Result<usize> {
-        // FIXME: (vcq): Wrap into vec?
-        let iov = IoSlice::new(buf);
-
-        let mut sendmsg = unsafe { MaybeUninit::<msghdr>::zeroed().assume_init() };
-        sendmsg.msg_name = addr.as_ptr() as *mut _;
-        sendmsg.msg_namelen = addr.len() as _;
-        sendmsg.msg_iov = iov.as_ptr() as *mut _;
-        sendmsg.msg_iovlen = iov.len();
+        let mut msg = MsgHdr::new(buf.as_ptr() as *mut _, buf.len());
+        msg.set_dest(addr);
 
         let fd = socket.as_raw_fd() as _;
 
-        let sqe = OP::SendMsg::new(Fd(fd), &sendmsg as *const _ as *const _)
-            .flags(SendFlags::empty())
-            .build();
+        let sqe = on_fd!(fd, |target| {
+            OP::SendMsg::new(target, &msg.hdr as *const _ as *const _)
+                .flags(SendFlags::empty())
+                .build()
+        });
 
-        let res = Proactor::get().inner().register_io(sqe)?.await?;
+        let res = sys_proactor()
+            .register_io_with(sqe, Cancellation::boxed(msg), timeout)?
+            .await?;
 
         Ok(res as _)
     }
@@ -348,8 +784,9 @@ impl Processor {
     pub(crate) async fn processor_recv_from<R: AsRawFd>(
         sock: &R,
         buf: &mut [u8],
+        timeout: Option<Duration>,
     ) -> io::Result<(usize, SocketAddr)> {
-        Self::recv_from_with_flags(sock, buf, RecvFlags::empty())
+        Self::recv_from_with_flags(sock, buf, RecvFlags::empty(), timeout)
             .await
             .map(|(size, sockaddr)| (size, sockaddr.as_std().unwrap()))
     }
@@ -357,8 +794,9 @@ impl Processor {
     pub(crate) async fn processor_peek_from<R: AsRawFd>(
         sock: &R,
         buf: &mut [u8],
+        timeout: Option<Duration>,
     ) -> io::Result<(usize, SocketAddr)> {
-        Self::recv_from_with_flags(sock, buf, RecvFlags::PEEK)
+        Self::recv_from_with_flags(sock, buf, RecvFlags::PEEK, timeout)
             .await
             .map(|(size, sockaddr)| (size, sockaddr.as_std().unwrap()))
     }
@@ -367,32 +805,30 @@ impl Processor {
         socket: &R,
         buf: &mut [u8],
         flags: RecvFlags,
+        timeout: Option<Duration>,
     ) -> io::Result<(usize, socket2::SockAddr)> {
-        let mut sockaddr_raw =
-            unsafe { MaybeUninit::<libc::sockaddr_storage>::zeroed().assume_init() };
-
-        // FIXME: (vcq): Wrap into vec?
-        let iov = IoSliceMut::new(buf);
-
-        let mut recvmsg = unsafe { MaybeUninit::<msghdr>::zeroed().assume_init() };
-        recvmsg.msg_name = &mut sockaddr_raw as *mut _ as _;
-        recvmsg.msg_namelen = std::mem::size_of::<libc::sockaddr_storage>() as _;
-        recvmsg.msg_iov = iov.as_ptr() as *mut _;
-        recvmsg.msg_iovlen = iov.len();
+        let msg = MsgHdr::new(buf.as_mut_ptr(), buf.len());
+        let msg_ptr = Box::into_raw(msg);
 
         let fd = socket.as_raw_fd() as _;
 
-        let sqe = OP::RecvMsg::new(Fd(fd), &mut recvmsg as *mut _ as *mut _)
-            .flags(flags)
-            .ioprio((IoringRecvFlags::POLL_FIRST | IoringRecvFlags::MULTISHOT).bits())
-            .build();
+        let sqe = on_fd!(fd, |target| {
+            OP::RecvMsg::new(target, unsafe { &mut (*msg_ptr).hdr } as *mut _ as *mut _)
+                .flags(flags)
+                // Multishot receive needs a provided buffer, a single message is received here.
+                .ioprio(IoringRecvFlags::POLL_FIRST.bits())
+                .build()
+        });
 
-        let res = Proactor::get().inner().register_io(sqe)?.await?;
+        let resources = Cancellation::boxed(unsafe { Box::from_raw(msg_ptr) });
+        let mut cc = sys_proactor().register_io_with(sqe, resources, timeout)?;
+        // Message header is owned by the completion until it is dropped.
+        let res = (&mut cc).await?;
 
         let sockaddr = unsafe {
             socket2::SockAddr::from_raw_parts(
-                &sockaddr_raw as *const _ as *const _,
-                recvmsg.msg_namelen as _,
+                &(*msg_ptr).name as *const _ as *const _,
+                (*msg_ptr).hdr.msg_namelen as _,
             )
         };
 
@@ -405,6 +841,7 @@ impl Processor {
 
     pub(crate) async fn processor_accept_unix_listener<R: AsRawFd>(
         listener: &R,
+        timeout: Option<Duration>,
     ) -> io::Result<(Handle<UnixStream>, UnixSocketAddr)> {
         let fd = listener.as_raw_fd() as _;
         let sockfd: OwnedFd = unsafe { OwnedFd::from_raw_fd(fd) };
@@ -412,25 +849,31 @@ impl Processor {
             SocketAddrAny::Unix(sockaddr) => sockaddr,
             _ => return Err(io::Error::last_os_error()),
         };
-        let mut natsockaddr: ShimSocketAddrUnix = unsafe { std::mem::transmute(sockaddr) };
-
-        let sqe = OP::Accept::new(
-            Fd(fd),
-            &mut natsockaddr.unix as *mut _ as *mut _,
-            natsockaddr.len as _,
-        )
-        .flags(SocketFlags::empty())
-        .build();
+        let natsockaddr: ShimSocketAddrUnix = unsafe { std::mem::transmute(sockaddr) };
+        let natsockaddr = Box::into_raw(Box::new(natsockaddr));
+
+        let sqe = on_fd!(fd, |target| {
+            OP::Accept::new(
+                target,
+                unsafe { &mut (*natsockaddr).unix } as *mut _ as *mut _,
+                unsafe { (*natsockaddr).len } as _,
+            )
+            .flags(SocketFlags::empty())
+            .build()
+        });
 
-        let cc = Proactor::get().inner().register_io(sqe)?;
+        let resources = Cancellation::boxed(unsafe { Box::from_raw(natsockaddr) });
+        let mut cc = sys_proactor().register_io_with(sqe, resources, timeout)?;
 
-        let stream = unsafe { UnixStream::from_raw_fd(cc.await?) };
+        // Socket address is owned by the completion until it is dropped.
+        let stream = unsafe { UnixStream::from_raw_fd((&mut cc).await?) };
         let usa = unsafe {
             socket2::SockAddr::from_raw_parts(
-                &natsockaddr.unix as *const _ as *const _,
-                natsockaddr.len as _,
+                &(*natsockaddr).unix as *const _ as *const _,
+                (*natsockaddr).len as _,
             )
         };
+        drop(cc);
         let addr = shim_to_af_unix(&usa)?;
 
         Ok((Handle::new(stream)?, addr))
@@ -453,10 +896,13 @@ impl Processor {
         let stream: UnixStream = sock.into_unix_stream();
         let fd = stream.as_raw_fd() as _;
 
+        let sockaddr = Box::new(sockaddr);
         let sqe =
             OP::Connect::new(Fd(fd), &sockaddr.unix as *const _ as *const _, sockaddr.len).build();
 
-        Proactor::get().inner().register_io(sqe)?.await?;
+        sys_proactor()
+            .register_io_with(sqe, Cancellation::boxed(sockaddr), None)?
+            .await?;
 
         Handle::new(stream)
     }
@@ -465,15 +911,17 @@ impl Processor {
         socket: &R,
         buf: &[u8],
         path: P,
+        timeout: Option<Duration>,
     ) -> io::Result<usize> {
-        Self::send_to_dest(socket, buf, &socket2::SockAddr::unix(path)?).await
+        Self::send_to_dest(socket, buf, &socket2::SockAddr::unix(path)?, timeout).await
     }
 
     pub(crate) async fn processor_recv_from_unix<R: AsRawFd>(
         socket: &R,
         buf: &mut [u8],
+        timeout: Option<Duration>,
     ) -> io::Result<(usize, UnixSocketAddr)> {
-        Self::recv_from_with_flags(socket, buf, RecvFlags::empty())
+        Self::recv_from_with_flags(socket, buf, RecvFlags::empty(), timeout)
             .await
             .map(|(size, sockaddr)| (size, shim_to_af_unix(&sockaddr).unwrap()))
     }
@@ -481,13 +929,51 @@ impl Processor {
     pub(crate) async fn processor_peek_from_unix<R: AsRawFd>(
         socket: &R,
         buf: &mut [u8],
+        timeout: Option<Duration>,
     ) -> io::Result<(usize, UnixSocketAddr)> {
-        Self::recv_from_with_flags(socket, buf, RecvFlags::PEEK)
+        Self::recv_from_with_flags(socket, buf, RecvFlags::PEEK, timeout)
             .await
             .map(|(size, sockaddr)| (size, shim_to_af_unix(&sockaddr).unwrap()))
     }
 }
 
+///
+/// Message header of `sendmsg` and `recvmsg` along with the memory it points to.
+/// It is boxed, so it stays at the same address until the kernel is done with it.
+struct MsgHdr {
+    hdr: msghdr,
+    iov: libc::iovec,
+    name: libc::sockaddr_storage,
+}
+
+unsafe impl Send for MsgHdr {}
+
+impl MsgHdr {
+    fn new(buf: *mut u8, len: usize) -> Box<MsgHdr> {
+        let mut msg: Box<MsgHdr> = Box::new(unsafe { MaybeUninit::zeroed().assume_init() });
+        msg.iov = libc::iovec {
+            iov_base: buf as *mut _,
+            iov_len: len,
+        };
+        msg.hdr.msg_iov = &mut msg.iov as *mut _ as *mut _;
+        msg.hdr.msg_iovlen = 1;
+        msg.hdr.msg_name = &mut msg.name as *mut _ as *mut _;
+        msg.hdr.msg_namelen = std::mem::size_of::<libc::sockaddr_storage>() as _;
+        msg
+    }
+
+    fn set_dest(&mut self, addr: &socket2::SockAddr) {
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                addr.as_ptr() as *const u8,
+                &mut self.name as *mut _ as *mut u8,
+                addr.len() as _,
+            );
+        }
+        self.hdr.msg_namelen = addr.len() as _;
+    }
+}
+
 #[derive(Clone)]
 pub struct ShimSocketAddrUnix {
     pub unix: sockaddr_un,
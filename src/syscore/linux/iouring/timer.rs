@@ -0,0 +1,72 @@
+use super::fs::cancellation::Cancellation;
+use super::{sys_proactor, CompletionChan};
+use crate::Proactor;
+use rustix_uring::opcode as OP;
+use rustix_uring::types::{TimeoutFlags, Timespec};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// `IORING_TIMEOUT_MULTISHOT`, available since Linux 6.4.
+const TIMEOUT_MULTISHOT: u32 = 1 << 6;
+
+///
+/// Timer that is driven by `IORING_OP_TIMEOUT` submissions.
+///
+/// Periodic timers are submitted as a single multishot timeout, on kernels that don't support
+/// multishot timeouts they are rearmed after every tick.
+pub(crate) struct Timer {
+    proactor: Proactor,
+    period: Option<Duration>,
+    multishot: bool,
+    chan: CompletionChan,
+}
+
+impl Timer {
+    pub(crate) fn new(duration: Duration, periodic: bool) -> io::Result<Timer> {
+        let proactor = Proactor::current();
+        let chan = Self::arm(&proactor, duration, periodic)?;
+
+        Ok(Timer {
+            proactor,
+            period: periodic.then_some(duration),
+            multishot: periodic,
+            chan,
+        })
+    }
+
+    fn arm(proactor: &Proactor, delay: Duration, multishot: bool) -> io::Result<CompletionChan> {
+        // Timespec is read by the kernel when the timeout is issued.
+        let ts = Box::new(Timespec::from(delay));
+        let flags = if multishot {
+            TimeoutFlags::from_bits_retain(TIMEOUT_MULTISHOT)
+        } else {
+            TimeoutFlags::empty()
+        };
+        let sqe = OP::Timeout::new(&*ts).flags(flags).build();
+
+        let _guard = proactor.enter();
+        sys_proactor().register_io_with(sqe, Cancellation::boxed(ts), None)
+    }
+
+    pub(crate) fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match futures::ready!(Pin::new(&mut self.chan).poll(cx)) {
+            Ok(_) => {}
+            Err(e) if e.raw_os_error() == Some(libc::ETIME) => {}
+            Err(e) if self.multishot && e.raw_os_error() == Some(libc::EINVAL) => {
+                self.multishot = false;
+                self.chan = Self::arm(&self.proactor, self.period.unwrap_or_default(), false)?;
+                return self.poll_tick(cx);
+            }
+            Err(e) => return Poll::Ready(Err(e)),
+        }
+
+        if let (Some(period), false) = (self.period, self.multishot) {
+            self.chan = Self::arm(&self.proactor, period, false)?;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
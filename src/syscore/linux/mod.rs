@@ -1,9 +1,14 @@
-#[cfg(feature = "epoll")]
+// Epoll is always available on Linux, io_uring is probed at runtime when it is compiled in.
 mod epoll;
-#[cfg(feature = "epoll")]
+#[cfg(not(feature = "iouring"))]
 pub(crate) use epoll::*;
 
+#[cfg(feature = "iouring")]
+mod dispatch;
 #[cfg(feature = "iouring")]
 mod iouring;
 #[cfg(feature = "iouring")]
-pub(crate) use iouring::*;
+pub(crate) use dispatch::*;
+
+mod shutdown;
+pub(crate) use shutdown::*;
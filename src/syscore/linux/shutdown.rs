@@ -0,0 +1,49 @@
+use std::io;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+const RUNNING: u8 = 0;
+const DRAINING: u8 = 1;
+const STOPPED: u8 = 2;
+
+///
+/// Lifecycle of a proactor, see [Proactor::shutdown](crate::Proactor::shutdown).
+pub(crate) struct ShutdownState(AtomicU8);
+
+impl ShutdownState {
+    pub(crate) fn new() -> ShutdownState {
+        ShutdownState(AtomicU8::new(RUNNING))
+    }
+
+    /// Whether new operations are accepted.
+    pub(crate) fn is_running(&self) -> bool {
+        self.0.load(Ordering::Acquire) == RUNNING
+    }
+
+    /// Whether the in-flight operations are completed or cancelled, and waiters are released.
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::Acquire) == STOPPED
+    }
+
+    /// Stops accepting new operations, in-flight ones are still completed.
+    pub(crate) fn drain(&self) {
+        let _ = self
+            .0
+            .compare_exchange(RUNNING, DRAINING, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    pub(crate) fn stop(&self) {
+        self.0.store(STOPPED, Ordering::Release);
+    }
+
+    /// Error of the operations that are submitted after shutdown has begun.
+    pub(crate) fn check(&self) -> io::Result<()> {
+        if self.is_running() {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "nuclei: proactor is shut down",
+            ))
+        }
+    }
+}
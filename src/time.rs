@@ -0,0 +1,101 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::future::Either;
+use futures::Stream;
+
+use crate::syscore::Timer;
+
+///
+/// Future that completes after the given duration, returned by [sleep].
+pub struct Sleep {
+    timer: Option<io::Result<Timer>>,
+}
+
+impl Future for Sleep {
+    type Output = io::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let res = match self.timer.as_mut() {
+            Some(Ok(timer)) => futures::ready!(timer.poll_tick(cx)),
+            Some(Err(_)) => self.timer.take().unwrap().map(drop),
+            // Already elapsed.
+            None => Ok(()),
+        };
+
+        self.timer = None;
+        Poll::Ready(res)
+    }
+}
+
+///
+/// Sleeps for the given duration.
+///
+/// The timer is armed when this function is called, and it is driven by the proactor along with
+/// the IO completions: io_uring uses `IORING_OP_TIMEOUT` submissions, epoll uses a timerfd.
+pub fn sleep(duration: Duration) -> Sleep {
+    Sleep {
+        timer: Some(Timer::new(duration, false)),
+    }
+}
+
+///
+/// Ticks once every period, returned by [interval].
+///
+/// Ticks that are missed while nobody is waiting on the interval are coalesced.
+pub struct Interval {
+    timer: Timer,
+}
+
+impl Interval {
+    ///
+    /// Waits until the next tick.
+    pub async fn tick(&mut self) -> io::Result<()> {
+        futures::future::poll_fn(|cx| self.timer.poll_tick(cx)).await
+    }
+}
+
+impl Stream for Interval {
+    type Item = io::Result<()>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.timer.poll_tick(cx).map(Some)
+    }
+}
+
+///
+/// Creates an interval that ticks once every `period`, first tick is after a whole period.
+///
+/// On io_uring this is a single multishot timeout on kernels that support it (6.4+).
+pub fn interval(period: Duration) -> io::Result<Interval> {
+    if period.is_zero() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "interval period must be non-zero",
+        ));
+    }
+
+    Ok(Interval {
+        timer: Timer::new(period, true)?,
+    })
+}
+
+///
+/// Awaits the future for at most the given duration.
+///
+/// If the future doesn't complete in time, it is dropped and [io::ErrorKind::TimedOut] is returned.
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> io::Result<F::Output> {
+    futures::pin_mut!(future);
+
+    match futures::future::select(future, sleep(duration)).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right((Ok(()), _)) => Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "deadline has elapsed",
+        )),
+        Either::Right((Err(e), _)) => Err(e),
+    }
+}
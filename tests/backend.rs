@@ -0,0 +1,70 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn epoll_backend_selected_at_runtime() -> std::io::Result<()> {
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::fs::File;
+    use std::net::{TcpListener, TcpStream};
+    use std::path::PathBuf;
+
+    let _ = Proactor::with_config(NucleiConfig {
+        backend: Some(IoBackend::Epoll),
+        ..NucleiConfig::default()
+    });
+    assert_eq!(Proactor::backend(), IoBackend::Epoll);
+
+    drive(async {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("testdata");
+        path.push("quark-gluon-plasma");
+
+        let mut file = Handle::<File>::new(File::open(&path)?)?;
+        let mut buffer = String::new();
+        file.read_to_string(&mut buffer).await?;
+        assert_eq!(11587, buffer.len());
+
+        let listener = Handle::<TcpListener>::bind("127.0.0.1:0")?;
+        let addr = listener.get_ref().local_addr()?;
+        let mut client = Handle::<TcpStream>::connect(addr).await?;
+        let (mut server, _) = listener.accept().await?;
+
+        client.write_all(b"nuclei").await?;
+        let mut echo = [0; 6];
+        server.read_exact(&mut echo).await?;
+        assert_eq!(&echo, b"nuclei");
+
+        server.set_read_timeout(Some(std::time::Duration::from_millis(50)));
+        let err = server.recv(&mut echo).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+        let mut interval = time::interval(std::time::Duration::from_millis(10))?;
+        interval.tick().await?;
+        interval.tick().await?;
+        time::sleep(std::time::Duration::from_millis(10)).await?;
+
+        Ok(())
+    })
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn auto_backend_reports_invalid_io_uring_config() {
+    use nuclei::config::{IoUringConfiguration, NucleiConfig};
+    use nuclei::*;
+
+    // Invalid configuration isn't a reason to fall back to epoll.
+    let err = Proactor::new(NucleiConfig {
+        backend: None,
+        iouring: IoUringConfiguration {
+            defer_taskrun: true,
+            single_issuer: false,
+            ..IoUringConfiguration::interrupt_driven(64)
+        },
+        ..NucleiConfig::default()
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,85 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+fn read_concurrently(
+    backpressure: nuclei::config::SubmissionBackpressure,
+) -> std::io::Result<(nuclei::Proactor, Vec<std::io::Result<usize>>)> {
+    use futures::AsyncReadExt;
+    use nuclei::config::{IoBackend, IoUringConfiguration, NucleiConfig};
+    use nuclei::*;
+    use std::fs::File;
+    use std::path::PathBuf;
+
+    let proactor = Proactor::new(NucleiConfig {
+        backend: Some(IoBackend::IoUring),
+        iouring: IoUringConfiguration {
+            queue_len: 4,
+            backpressure,
+            ..IoUringConfiguration::default()
+        },
+        ..NucleiConfig::default()
+    })?;
+    let _guard = proactor.enter();
+
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("testdata");
+    path.push("quark-gluon-plasma");
+
+    let reads = (0..128).map(|_| {
+        let path = path.clone();
+        async move {
+            let mut file = Handle::<File>::new(File::open(&path)?)?;
+            let mut buffer = String::new();
+            file.read_to_string(&mut buffer).await?;
+            Ok::<_, std::io::Error>(buffer.len())
+        }
+    });
+
+    let sizes = drive(futures::future::join_all(reads));
+    Ok((proactor, sizes))
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn overflowing_submission_queue() -> std::io::Result<()> {
+    use nuclei::config::SubmissionBackpressure;
+
+    let (proactor, sizes) = read_concurrently(SubmissionBackpressure::Overflow)?;
+    assert!(sizes.iter().all(|size| *size.as_ref().unwrap() == 11587));
+    assert!(proactor.sq_full_events() > 0);
+
+    Ok(())
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn waiting_for_submission_queue() -> std::io::Result<()> {
+    use nuclei::config::SubmissionBackpressure;
+
+    let (proactor, sizes) = read_concurrently(SubmissionBackpressure::Wait)?;
+    assert!(sizes.iter().all(|size| *size.as_ref().unwrap() == 11587));
+    assert!(proactor.sq_full_events() > 0);
+
+    Ok(())
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn full_submission_queue_would_block() -> std::io::Result<()> {
+    use nuclei::config::SubmissionBackpressure;
+    use std::io::ErrorKind;
+
+    let (proactor, sizes) = read_concurrently(SubmissionBackpressure::WouldBlock)?;
+    assert!(proactor.sq_full_events() > 0);
+    assert!(sizes.iter().any(|size| size.is_ok()));
+    for size in sizes {
+        match size {
+            Ok(size) => assert_eq!(size, 11587),
+            Err(e) => assert_eq!(e.kind(), ErrorKind::WouldBlock),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn batched_submissions_without_sqpoll() -> std::io::Result<()> {
+    use futures::AsyncReadExt;
+    use nuclei::config::{IoUringConfiguration, NucleiConfig, SubmissionBatching};
+    use nuclei::*;
+    use std::fs::File;
+    use std::path::PathBuf;
+
+    let _ = Proactor::with_config(NucleiConfig {
+        iouring: IoUringConfiguration {
+            batching: SubmissionBatching::Threshold(8),
+            ..IoUringConfiguration::interrupt_driven(64)
+        },
+        ..NucleiConfig::default()
+    });
+
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("testdata");
+    path.push("quark-gluon-plasma");
+
+    // Fewer operations than the threshold must still complete on driver ticks.
+    let mut file = Handle::<File>::new(File::open(&path)?)?;
+    let mut buffer = String::new();
+    drive(file.read_to_string(&mut buffer))?;
+    assert_eq!(11587, buffer.len());
+
+    let reads = (0..32).map(|_| {
+        let path = path.clone();
+        async move {
+            let mut file = Handle::<File>::new(File::open(&path)?)?;
+            let mut buffer = String::new();
+            file.read_to_string(&mut buffer).await?;
+            Ok::<_, std::io::Error>(buffer.len())
+        }
+    });
+
+    let sizes = drive(futures::future::try_join_all(reads))?;
+    assert!(sizes.iter().all(|size| *size == 11587));
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn dropped_recv_is_cancelled() -> std::io::Result<()> {
+    use futures::AsyncReadExt;
+    use nuclei::*;
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    let (local, mut remote) = UnixStream::pair()?;
+    let mut observer = local.try_clone()?;
+    observer.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    drive(async {
+        let mut stream = Handle::<UnixStream>::new(local)?;
+        let mut buf = [0_u8; 4];
+        let read = stream.read(&mut buf);
+        futures::pin_mut!(read);
+        assert!(futures::poll!(read).is_pending());
+        // Let the kernel arm the receive before the handle is dropped.
+        std::thread::sleep(Duration::from_millis(100));
+        Ok::<_, std::io::Error>(())
+    })?;
+
+    // Receive submitted above is cancelled, so the data isn't consumed by it.
+    remote.write_all(b"ping")?;
+    // Give a receive that is still in flight the chance to consume the data.
+    std::thread::sleep(Duration::from_millis(100));
+    let mut buf = [0_u8; 4];
+    observer.read_exact(&mut buf)?;
+    assert_eq!(&buf, b"ping");
+
+    Ok(())
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn dropped_recv_is_acknowledged_before_the_borrow_ends() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, IoUringConfiguration, NucleiConfig, SubmissionBatching};
+    use nuclei::*;
+    use std::os::unix::net::UnixStream;
+
+    // Cancellation is batched along with the receive, it mustn't wait for the threshold.
+    let proactor = Proactor::new(NucleiConfig {
+        backend: Some(IoBackend::IoUring),
+        iouring: IoUringConfiguration {
+            batching: SubmissionBatching::Threshold(32),
+            ..IoUringConfiguration::interrupt_driven(64)
+        },
+        ..NucleiConfig::default()
+    })?;
+    let _guard = proactor.enter();
+
+    drive(async {
+        let (local, _remote) = Handle::<UnixStream>::pair()?;
+        let mut buf = vec![0_u8; 4];
+        {
+            let recv = local.recv(&mut buf);
+            futures::pin_mut!(recv);
+            assert!(futures::poll!(recv).is_pending());
+        }
+        // Kernel is done with the buffer by the time the receive is dropped.
+        assert_eq!(proactor.stats().inflight, 0);
+        drop(buf);
+
+        Ok::<_, std::io::Error>(())
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn capabilities_and_accept_fallback() -> std::io::Result<()> {
+    use futures::{AsyncReadExt, AsyncWriteExt, StreamExt};
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::net::{TcpListener, TcpStream};
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            ..NucleiConfig::default()
+        })?;
+        let capabilities = proactor.capabilities();
+        match backend {
+            // Probing needs 5.6+, the rest follows the kernel.
+            IoBackend::IoUring => assert!(capabilities.provide_buffers || !capabilities.socket),
+            _ => assert_eq!(capabilities, Capabilities::default()),
+        }
+
+        let _guard = proactor.enter();
+        drive(async {
+            let listener = Handle::<TcpListener>::bind("127.0.0.1:0")?;
+            let addr = listener.get_ref().local_addr()?;
+            let mut streams = listener.accept_multi().await?;
+
+            for _ in 0..2 {
+                let mut client = Handle::<TcpStream>::connect(addr).await?;
+                let mut server = streams.next().await.unwrap();
+                client.write_all(b"nuclei").await?;
+                let mut buf = [0; 6];
+                server.read_exact(&mut buf).await?;
+                assert_eq!(&buf, b"nuclei");
+            }
+            Ok::<_, std::io::Error>(())
+        })?;
+    }
+
+    Ok(())
+}
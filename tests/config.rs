@@ -0,0 +1,94 @@
+#[test]
+fn parse_config_file() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, NucleiConfig, SubmissionBackpressure, SubmissionBatching};
+
+    let config = NucleiConfig::parse(
+        r#"
+        # Tuned for the storage hosts.
+        backend = "iouring"
+
+        [iouring]
+        queue_len = 512
+        preset = "interrupt_driven"
+        sqpoll_ms = none
+        backpressure = "overflow" # park instead of blocking
+        batching = 8
+        coop_taskrun = true
+        "#,
+    )?;
+    assert_eq!(config.backend, Some(IoBackend::IoUring));
+    assert_eq!(config.iouring.queue_len, 512);
+    assert_eq!(config.iouring.sqpoll_wake_interval, None);
+    assert!(!config.iouring.aggressive_poll);
+    assert_eq!(config.iouring.backpressure, SubmissionBackpressure::Overflow);
+    assert_eq!(config.iouring.batching, SubmissionBatching::Threshold(8));
+    assert!(config.iouring.coop_taskrun);
+
+    for (bad, msg) in [
+        ("queue_len = lots", "invalid value `lots` for `queue_len` at line 1"),
+        ("preset = fastest", "`interrupt_driven`, `low_latency_driven`"),
+        ("queue_length = 8", "unknown key `queue_length` at line 1"),
+        ("[epol]", "unknown section `epol`"),
+        ("\nclamp", "expected `key = value` at line 2"),
+    ] {
+        let err = NucleiConfig::parse(bad).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains(msg), "{}", err);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn config_from_env() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, NucleiConfig};
+
+    std::env::set_var("NUCLEI_BACKEND", "epoll");
+    std::env::set_var("NUCLEI_PRESET", "kernel_poll_only");
+    std::env::set_var("NUCLEI_QUEUE_LEN", "64");
+    std::env::set_var("NUCLEI_SQPOLL_MS", "5");
+    let config = NucleiConfig::from_env()?;
+    assert_eq!(config.backend, Some(IoBackend::Epoll));
+    assert_eq!(config.iouring.queue_len, 64);
+    assert_eq!(config.iouring.sqpoll_wake_interval, Some(5));
+    assert!(!config.iouring.aggressive_poll);
+
+    std::env::set_var("NUCLEI_AGGRESSIVE_POLL", "sometimes");
+    let err = NucleiConfig::from_env().unwrap_err();
+    assert!(err.to_string().contains("`NUCLEI_AGGRESSIVE_POLL`"), "{}", err);
+    std::env::remove_var("NUCLEI_AGGRESSIVE_POLL");
+
+    std::env::set_var("NUCLEI_QUEUELEN", "64");
+    let err = NucleiConfig::from_env().unwrap_err();
+    assert!(err.to_string().contains("unknown environment variable `NUCLEI_QUEUELEN`"));
+
+    Ok(())
+}
+
+#[test]
+fn parse_epoll_section() -> std::io::Result<()> {
+    use nuclei::config::{EpollBusyPoll, EpollTrigger, NucleiConfig};
+
+    let config = NucleiConfig::parse(
+        "[epoll]\nepoll_trigger = level\nepoll_exclusive = true\nepoll_busy_poll_usecs = 50\n",
+    )?;
+    assert_eq!(config.epoll.trigger, EpollTrigger::Level);
+    assert!(config.epoll.exclusive);
+    assert_eq!(
+        config.epoll.busy_poll,
+        Some(EpollBusyPoll {
+            usecs: 50,
+            budget: 8,
+            prefer: false
+        })
+    );
+
+    let err = NucleiConfig::parse("epoll_busy_poll_budget = 16").unwrap_err();
+    assert!(
+        err.to_string().contains("requires `epoll_busy_poll_usecs`"),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
@@ -0,0 +1,146 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn direct_descriptors_in_the_file_table() -> std::io::Result<()> {
+    use futures::StreamExt;
+    use nuclei::config::{IoBackend, IoUringConfiguration, NucleiConfig};
+    use nuclei::*;
+    use std::net::{TcpListener, TcpStream};
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            iouring: IoUringConfiguration {
+                file_table_len: 8,
+                ..IoUringConfiguration::interrupt_driven(64)
+            },
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+
+        drive(async {
+            let mut listener = Handle::<TcpListener>::bind("127.0.0.1:0")?;
+            let addr = listener.get_ref().local_addr()?;
+
+            if backend == IoBackend::Epoll || !proactor.capabilities().direct_files {
+                let err = listener.register_direct().await.err().unwrap();
+                assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+                assert_eq!(listener.direct_slot(), None);
+                return Ok(());
+            }
+
+            let slot = listener.register_direct().await?;
+            assert_eq!(listener.register_direct().await?, slot);
+            assert_eq!(listener.direct_slot(), Some(slot));
+
+            let mut streams = listener.accept_multi().await?;
+            let mut client = Handle::<TcpStream>::connect(addr).await?;
+            let mut server = streams.next().await.unwrap();
+            client.register_direct().await?;
+            server.register_direct().await?;
+            assert_ne!(client.direct_slot(), server.direct_slot());
+
+            client.send(b"quark-gluon").await?;
+            let mut buf = [0; 11];
+            let mut received = 0;
+            while received < buf.len() {
+                received += server.recv(&mut buf[received..]).await?;
+            }
+            assert_eq!(&buf, b"quark-gluon");
+
+            // Slot is closed along with the handle, so the peer sees the end of the stream.
+            drop(client);
+            assert_eq!(server.recv(&mut buf).await?, 0);
+
+            Ok::<_, std::io::Error>(())
+        })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn accepted_and_opened_straight_into_the_file_table() -> std::io::Result<()> {
+    use futures::StreamExt;
+    use nuclei::config::{IoBackend, IoUringConfiguration, NucleiConfig};
+    use nuclei::*;
+    use std::net::{TcpListener, TcpStream};
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            iouring: IoUringConfiguration {
+                file_table_len: 8,
+                ..IoUringConfiguration::interrupt_driven(64)
+            },
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+
+        drive(async {
+            let listener = Handle::<TcpListener>::bind("127.0.0.1:0")?;
+            let addr = listener.get_ref().local_addr()?;
+            let path = std::env::temp_dir().join(format!("nuclei-direct-{}", std::process::id()));
+            std::fs::write(&path, b"")?;
+
+            if backend == IoBackend::Epoll || !proactor.capabilities().direct_files {
+                let err = listener.accept_direct().await.err().unwrap();
+                assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+                let err = listener.accept_multi_direct().await.err().unwrap();
+                assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+                let err = Handle::open_direct(&path).await.err().unwrap();
+                assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+                std::fs::remove_file(&path)?;
+                return Ok(());
+            }
+
+            // Single-shot.
+            let client = Handle::<TcpStream>::connect(addr).await?;
+            let server = listener.accept_direct().await?;
+            client.send(b"quark").await?;
+            let mut buf = [0; 5];
+            let mut received = 0;
+            while received < buf.len() {
+                received += server.recv(&mut buf[received..]).await?;
+            }
+            assert_eq!(&buf, b"quark");
+            server.send(b"gluon").await?;
+            received = 0;
+            while received < buf.len() {
+                received += client.recv(&mut buf[received..]).await?;
+            }
+            assert_eq!(&buf, b"gluon");
+
+            // Slot is closed along with the handle, so the peer sees the end of the stream.
+            drop(server);
+            assert_eq!(client.recv(&mut buf).await?, 0);
+
+            // Multishot.
+            let mut streams = listener.accept_multi_direct().await?;
+            let first = Handle::<TcpStream>::connect(addr).await?;
+            let second = Handle::<TcpStream>::connect(addr).await?;
+            let (a, b) = (streams.next().await.unwrap(), streams.next().await.unwrap());
+            assert_ne!(a.slot(), b.slot());
+            first.send(b"q").await?;
+            second.send(b"q").await?;
+            assert_eq!(a.recv(&mut buf).await?, 1);
+            assert_eq!(b.recv(&mut buf).await?, 1);
+
+            // Files are read and written at offsets.
+            let file = Handle::open_direct(&path).await?;
+            assert_eq!(file.write_at(b"quark-gluon", 0).await?, 11);
+            let mut buf = [0; 5];
+            assert_eq!(file.read_at(&mut buf, 6).await?, 5);
+            assert_eq!(&buf, b"gluon");
+            drop(file);
+            assert_eq!(std::fs::read(&path)?, b"quark-gluon");
+            std::fs::remove_file(&path)?;
+
+            Ok::<_, std::io::Error>(())
+        })?;
+    }
+
+    Ok(())
+}
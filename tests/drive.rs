@@ -0,0 +1,77 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn drive_parks_until_woken() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::time::{Duration, Instant};
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+
+        // Nothing but the waker completes the future, the driving thread is woken out of the wait.
+        let (tx, rx) = futures::channel::oneshot::channel();
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            tx.send(7).unwrap();
+        });
+        let started = Instant::now();
+        assert_eq!(drive(rx), Ok(7), "{:?}", backend);
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        sender.join().unwrap();
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn drive_shares_proactor_between_threads() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            ..NucleiConfig::default()
+        })?;
+
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let proactor = proactor.clone();
+                std::thread::spawn(move || -> std::io::Result<()> {
+                    let _guard = proactor.enter();
+                    let (l, r) = UnixStream::pair()?;
+                    r.set_nonblocking(true)?;
+                    let (l, r) = (Handle::new(l)?, Handle::new(r)?);
+
+                    drive(async {
+                        // Threads finish one after another, so the driver slot is handed over.
+                        time::sleep(Duration::from_millis(10 * i)).await?;
+                        let writer = async {
+                            time::sleep(Duration::from_millis(5)).await?;
+                            l.send(b"nuclei").await
+                        };
+                        let mut buf = [0; 6];
+                        let (sent, received) = futures::join!(writer, r.recv(&mut buf));
+                        assert_eq!((sent?, received?), (6, 6));
+                        Ok(())
+                    })
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap()?;
+        }
+    }
+
+    Ok(())
+}
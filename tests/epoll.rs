@@ -0,0 +1,63 @@
+#[cfg(target_os = "linux")]
+#[test]
+fn epoll_configuration() -> std::io::Result<()> {
+    use nuclei::config::{
+        EpollBusyPoll, EpollConfiguration, EpollTrigger, IoBackend, NucleiConfig,
+    };
+    use nuclei::*;
+    use std::os::unix::net::UnixStream;
+
+    for (trigger, exclusive) in [(EpollTrigger::Level, false), (EpollTrigger::Edge, true)] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(IoBackend::Epoll),
+            epoll: EpollConfiguration {
+                max_events: Some(1),
+                trigger,
+                exclusive,
+                busy_poll: None,
+            },
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+        let (l, r) = UnixStream::pair()?;
+        l.set_nonblocking(true)?;
+        r.set_nonblocking(true)?;
+        let (l, r) = (Handle::new(l)?, Handle::new(r)?);
+
+        drive(async {
+            for _ in 0..3 {
+                // Both ends wait on reads at first, so the interests of each fd change.
+                let (mut lbuf, mut rbuf) = ([0; 6], [0; 6]);
+                let exchange = async {
+                    r.send(b"nuclei").await?;
+                    l.send(b"proton").await
+                };
+                let (sent, lrecv, rrecv) =
+                    futures::join!(exchange, l.recv(&mut lbuf), r.recv(&mut rbuf));
+                assert_eq!(sent?, 6);
+                assert_eq!((lrecv?, rrecv?), (6, 6));
+                assert_eq!((&lbuf, &rbuf), (b"nuclei", b"proton"));
+            }
+            Ok::<_, std::io::Error>(())
+        })?;
+    }
+
+    let busy_poll = Proactor::new(NucleiConfig {
+        backend: Some(IoBackend::Epoll),
+        epoll: EpollConfiguration {
+            busy_poll: Some(EpollBusyPoll {
+                usecs: 10,
+                budget: 8,
+                prefer: false,
+            }),
+            ..EpollConfiguration::default()
+        },
+        ..NucleiConfig::default()
+    });
+    // Busy polling needs 6.9+.
+    if let Err(e) = busy_poll {
+        assert_eq!(e.kind(), std::io::ErrorKind::Unsupported, "{}", e);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn fixed_buffers_on_files_and_sockets() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::fs::{File, OpenOptions};
+    use std::net::{TcpListener, TcpStream};
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+
+        let pool = FixedBufferPool::new(2, 4096)?;
+        if backend == IoBackend::IoUring {
+            let err = FixedBufferPool::new(1, 4096).err().unwrap();
+            assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        }
+
+        let mut path = std::env::temp_dir();
+        path.push(format!("nuclei-fixed-{:?}-{}", backend, std::process::id()));
+
+        drive(async {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&path)?;
+            let file = Handle::<File>::new(file)?;
+
+            let mut buf = pool.try_acquire().unwrap();
+            assert_eq!(buf.put_slice(b"quark-gluon"), 11);
+            let (n, buf) = file.write_fixed_at(buf, 5).await?;
+            assert_eq!(n, 11);
+            drop(buf);
+
+            let buf = pool.acquire().await;
+            let (n, buf) = file.read_fixed_at(buf, 5).await?;
+            assert_eq!(n, 11);
+            assert_eq!(&buf[..], b"quark-gluon");
+
+            let listener = Handle::<TcpListener>::bind("127.0.0.1:0")?;
+            let addr = listener.get_ref().local_addr()?;
+            let client = Handle::<TcpStream>::connect(addr).await?;
+            let (server, _) = listener.accept().await?;
+
+            let (n, buf) = client.send_fixed(buf).await?;
+            assert_eq!(n, 11);
+
+            let mut echo = pool.try_acquire().unwrap();
+            assert!(pool.try_acquire().is_none());
+            let mut received = Vec::new();
+            while received.len() < 11 {
+                let (_, back) = server.recv_fixed(echo).await?;
+                received.extend_from_slice(&back);
+                echo = back;
+            }
+            assert_eq!(&received, b"quark-gluon");
+
+            drop((buf, echo));
+            assert_eq!(pool.available(), 2);
+
+            Ok::<_, std::io::Error>(())
+        })?;
+
+        std::fs::remove_file(&path)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn try_with_config_reports_failures() {
+    use nuclei::config::{IoBackend, IoUringConfiguration, NucleiConfig};
+    use nuclei::*;
+
+    let err = Proactor::try_with_config(NucleiConfig {
+        backend: Some(IoBackend::IoUring),
+        iouring: IoUringConfiguration {
+            defer_taskrun: true,
+            ..IoUringConfiguration::default()
+        },
+        ..NucleiConfig::default()
+    })
+    .unwrap_err();
+    assert!(matches!(err, NucleiError::InvalidConfig(_)), "{:?}", err);
+
+    let err = Proactor::try_with_config(NucleiConfig {
+        backend: Some(IoBackend::Kqueue),
+        ..NucleiConfig::default()
+    })
+    .unwrap_err();
+    assert!(
+        matches!(err, NucleiError::UnsupportedKernel(_)),
+        "{:?}",
+        err
+    );
+
+    let proactor = Proactor::try_with_config(NucleiConfig {
+        backend: Some(IoBackend::Epoll),
+        ..NucleiConfig::default()
+    })
+    .unwrap();
+    assert_eq!(Proactor::get(), proactor);
+
+    let err = Proactor::try_with_config(NucleiConfig::default()).unwrap_err();
+    assert!(matches!(err, NucleiError::AlreadyInitialized));
+    let err = std::io::Error::from(err);
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+}
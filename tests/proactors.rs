@@ -0,0 +1,161 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn proactor_per_thread() -> std::io::Result<()> {
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::os::unix::net::UnixStream;
+
+    let workers = [IoBackend::IoUring, IoBackend::Epoll].map(|backend| {
+        std::thread::spawn(move || -> std::io::Result<()> {
+            let proactor = Proactor::new(NucleiConfig {
+                backend: Some(backend),
+                ..NucleiConfig::default()
+            })?;
+            let _guard = proactor.enter();
+            assert_eq!(Proactor::backend(), backend);
+
+            drive(async {
+                let (mut l, mut r) = Handle::<UnixStream>::pair()?;
+                assert!(l.proactor() == &proactor);
+                assert!(l.proactor() != Proactor::get());
+
+                l.write_all(b"nuclei").await?;
+                let mut buf = [0; 6];
+                r.read_exact(&mut buf).await?;
+                assert_eq!(&buf, b"nuclei");
+
+                Ok(())
+            })
+        })
+    });
+
+    for worker in workers {
+        worker.join().unwrap()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn handle_stays_with_its_proactor() -> std::io::Result<()> {
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::os::unix::net::UnixStream;
+
+    let proactor = Proactor::new(NucleiConfig {
+        backend: Some(IoBackend::Epoll),
+        ..NucleiConfig::default()
+    })?;
+    let (mut l, mut r) = {
+        let _guard = proactor.enter();
+        Handle::<UnixStream>::pair()?
+    };
+    assert!(Proactor::current() != proactor);
+
+    // Handles are polled from another thread which drives their proactor.
+    std::thread::spawn(move || {
+        let _guard = proactor.enter();
+        drive(async {
+            assert!(l.proactor() == &Proactor::current());
+            l.write_all(b"nuclei").await?;
+            let mut buf = [0; 6];
+            r.read_exact(&mut buf).await?;
+            assert_eq!(&buf, b"nuclei");
+
+            Ok(())
+        })
+    })
+    .join()
+    .unwrap()
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn shutdown_cancels_in_flight() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+        let (l, r) = UnixStream::pair()?;
+        l.set_nonblocking(true)?;
+        r.set_nonblocking(true)?;
+        let (l, r) = (Handle::new(l)?, Handle::new(r)?);
+
+        let stopper = {
+            let proactor = proactor.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                proactor.shutdown(ShutdownMode::Cancel)
+            })
+        };
+
+        let mut buf = [0; 6];
+        let err = drive(r.recv(&mut buf)).unwrap_err();
+        // ECANCELED
+        assert_eq!(err.raw_os_error(), Some(125), "{:?}", backend);
+        stopper.join().unwrap()?;
+        assert!(proactor.is_shutdown());
+
+        let err = drive(l.recv(&mut buf)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotConnected);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn shutdown_drains_in_flight() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::io::Write;
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+        let (mut peer, stream) = UnixStream::pair()?;
+        stream.set_nonblocking(true)?;
+        let stream = Handle::new(stream)?;
+
+        let stopper = {
+            let proactor = proactor.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                proactor.shutdown(ShutdownMode::Drain(Duration::from_secs(10)))
+            })
+        };
+        // Completes while the proactor is draining.
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            peer.write_all(b"nuclei")
+        });
+
+        let mut buf = [0; 6];
+        assert_eq!(drive(stream.recv(&mut buf))?, 6);
+        assert_eq!(&buf, b"nuclei");
+        writer.join().unwrap()?;
+        stopper.join().unwrap()?;
+        assert!(proactor.is_shutdown());
+    }
+
+    Ok(())
+}
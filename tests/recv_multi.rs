@@ -0,0 +1,72 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn recv_multi_streams_chunks_until_eof() -> std::io::Result<()> {
+    use futures::StreamExt;
+    use nuclei::config::{IoBackend, IoUringConfiguration, NucleiConfig};
+    use nuclei::*;
+    use std::net::{TcpListener, TcpStream};
+    use std::os::unix::net::UnixStream;
+
+    let data: Vec<u8> = (0..200u8).collect();
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        // Small ring, so the receive runs out of buffers and is armed again.
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            iouring: IoUringConfiguration {
+                recv_buf_ring_len: 2,
+                recv_buf_size: 16,
+                ..IoUringConfiguration::interrupt_driven(64)
+            },
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+
+        drive(async {
+            let listener = Handle::<TcpListener>::bind("127.0.0.1:0")?;
+            let addr = listener.get_ref().local_addr()?;
+            let client = Handle::<TcpStream>::connect(addr).await?;
+            let (server, _) = listener.accept().await?;
+
+            let mut chunks = server.recv_multi().await?;
+            for part in data.chunks(50) {
+                let mut sent = 0;
+                while sent < part.len() {
+                    sent += client.send(&part[sent..]).await?;
+                }
+            }
+            drop(client);
+
+            let mut received = Vec::new();
+            while let Some(chunk) = chunks.next().await {
+                let chunk = chunk?;
+                if backend == IoBackend::IoUring && proactor.capabilities().recv_multi {
+                    assert!(chunk.len() <= 16);
+                }
+                received.extend_from_slice(&chunk);
+            }
+            assert_eq!(received, data);
+
+            let (left, right) = Handle::<UnixStream>::pair()?;
+            let mut chunks = right.recv_multi().await?;
+            left.send(b"quark-gluon").await?;
+            let chunk = chunks.next().await.unwrap()?;
+            assert!(b"quark-gluon".starts_with(&chunk));
+            drop(chunks);
+
+            // Buffers of a dropped stream go back to the ring.
+            let mut chunks = right.recv_multi().await?;
+            drop(left);
+            let mut rest = Vec::new();
+            while let Some(chunk) = chunks.next().await {
+                rest.extend_from_slice(&chunk?);
+            }
+            assert!(b"quark-gluon".ends_with(&rest));
+
+            Ok::<_, std::io::Error>(())
+        })?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn advanced_setup_flags() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, IoUringConfiguration, NucleiConfig};
+    use nuclei::*;
+    use std::io::ErrorKind;
+    use std::os::unix::net::UnixStream;
+
+    let config = |iouring| NucleiConfig {
+        backend: Some(IoBackend::IoUring),
+        iouring,
+        ..NucleiConfig::default()
+    };
+
+    let shared = Proactor::new(config(IoUringConfiguration::interrupt_driven(16)))?;
+    let proactor = Proactor::new(config(IoUringConfiguration {
+        cq_len: Some(64),
+        clamp: true,
+        coop_taskrun: true,
+        submit_all: true,
+        attach_wq: shared.ring_fd(),
+        ..IoUringConfiguration::interrupt_driven(16)
+    }))?;
+    let params = proactor.ring_params().unwrap();
+    assert_eq!(params.sq_entries(), 16);
+    assert_eq!(params.cq_entries(), 64);
+
+    let _guard = proactor.enter();
+    let (l, r) = UnixStream::pair()?;
+    let (l, r) = (Handle::new(l)?, Handle::new(r)?);
+    drive(async {
+        l.send(b"nuclei").await?;
+        let mut buf = [0; 6];
+        assert_eq!(r.recv(&mut buf).await?, 6);
+        Ok::<_, std::io::Error>(())
+    })?;
+
+    let single = Proactor::new(config(IoUringConfiguration {
+        single_issuer: true,
+        defer_taskrun: true,
+        ..IoUringConfiguration::interrupt_driven(16)
+    }))?;
+    assert!(single.ring_params().unwrap().is_setup_single_issuer());
+
+    for invalid in [
+        IoUringConfiguration {
+            defer_taskrun: true,
+            ..IoUringConfiguration::interrupt_driven(16)
+        },
+        IoUringConfiguration {
+            sqpoll_cpu: Some(0),
+            ..IoUringConfiguration::interrupt_driven(16)
+        },
+        IoUringConfiguration {
+            cq_len: Some(8),
+            ..IoUringConfiguration::interrupt_driven(16)
+        },
+    ] {
+        let err = Proactor::new(config(invalid)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+#[cfg(target_os = "linux")]
+#[test]
+fn spawn_on_workers() -> std::io::Result<()> {
+    use nuclei::config::{IoUringConfiguration, NucleiConfig};
+    use nuclei::runtime::{spawn_local, Builder};
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    let rt = Builder::new()
+        .worker_threads(2)
+        .pin_to_cpus(true)
+        .config(NucleiConfig {
+            iouring: IoUringConfiguration::interrupt_driven(64),
+            ..NucleiConfig::default()
+        })
+        .thread_name("rt-test")
+        .build()?;
+    assert_eq!(rt.workers(), 2);
+
+    for worker in 0..rt.workers() {
+        let name = rt.block_on(rt.spawn_on(worker, async {
+            // Local tasks don't need to be Send.
+            let local = Rc::new(std::thread::current().name().unwrap().to_owned());
+            spawn_local(async move {
+                nuclei::time::sleep(Duration::from_millis(1)).await?;
+                Ok::<_, std::io::Error>(local.to_string())
+            })
+            .await?
+        }))??;
+        assert_eq!(name, format!("rt-test-{}", worker));
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn distribute_connections() -> std::io::Result<()> {
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use nuclei::runtime::Builder;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+
+    let rt = Builder::new().worker_threads(2).build()?;
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+
+    let clients = (0..4)
+        .map(|i| {
+            std::thread::spawn(move || -> std::io::Result<Vec<u8>> {
+                let mut stream = TcpStream::connect(addr)?;
+                stream.write_all(&[i])?;
+                let mut buf = Vec::new();
+                stream.read_to_end(&mut buf)?;
+                Ok(buf)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut served = Vec::new();
+    for _ in 0..clients.len() {
+        let (stream, _) = listener.accept()?;
+        stream.set_nonblocking(true)?;
+        served.push(rt.distribute(stream, |mut stream| async move {
+            let mut buf = [0; 1];
+            stream.read_exact(&mut buf).await?;
+            let name = std::thread::current().name().unwrap().to_owned();
+            stream.write_all(&[buf[0], b':']).await?;
+            stream.write_all(name.as_bytes()).await?;
+            Ok(name)
+        }));
+    }
+
+    let mut names = served
+        .into_iter()
+        .map(|task| rt.block_on(task)?)
+        .collect::<std::io::Result<Vec<_>>>()?;
+    names.sort();
+    names.dedup();
+    assert_eq!(names, ["nuclei-worker-0", "nuclei-worker-1"]);
+
+    for (i, client) in clients.into_iter().enumerate() {
+        let reply = client.join().unwrap()?;
+        assert_eq!(reply[0], i as u8);
+        assert_eq!(reply[1], b':');
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn block_on_from_worker_panics() -> std::io::Result<()> {
+    use nuclei::runtime::Builder;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::Arc;
+
+    let rt = Arc::new(Builder::new().worker_threads(1).build()?);
+    let inner = rt.clone();
+    let nested = async move { catch_unwind(AssertUnwindSafe(|| inner.block_on(async {}))) };
+    assert!(rt.block_on(nested).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn zero_copy_sends_return_the_buffer() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::net::{TcpListener, TcpStream, UdpSocket};
+
+    let data: Vec<u8> = (0..1 << 18).map(|i| i as u8).collect();
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+
+        drive(async {
+            let listener = Handle::<TcpListener>::bind("127.0.0.1:0")?;
+            let addr = listener.get_ref().local_addr()?;
+            let client = Handle::<TcpStream>::connect(addr).await?;
+            let (server, _) = listener.accept().await?;
+
+            let send = async {
+                let mut buf = data.clone();
+                let mut sent = 0;
+                while !buf.is_empty() {
+                    let (n, mut back) = client.send_zc(buf).await?;
+                    sent += n;
+                    buf = back.split_off(n);
+                }
+                Ok::<_, std::io::Error>(sent)
+            };
+            let recv = async {
+                let mut received = Vec::new();
+                let mut chunk = vec![0; 1 << 16];
+                while received.len() < data.len() {
+                    let n = server.recv(&mut chunk).await?;
+                    received.extend_from_slice(&chunk[..n]);
+                }
+                Ok::<_, std::io::Error>(received)
+            };
+            let (sent, received) = futures::join!(send, recv);
+            assert_eq!(sent?, data.len());
+            assert_eq!(received?, data);
+
+            let sender = Handle::<UdpSocket>::bind("127.0.0.1:0")?;
+            let receiver = Handle::<UdpSocket>::bind("127.0.0.1:0")?;
+            let to = receiver.get_ref().local_addr()?;
+            let (n, buf) = sender.send_to_zc(*b"quark-gluon", to).await?;
+            assert_eq!(n, 11);
+            assert_eq!(&buf, b"quark-gluon");
+
+            let mut dgram = [0; 32];
+            let n = receiver.recv(&mut dgram).await?;
+            assert_eq!(&dgram[..n], b"quark-gluon");
+
+            Ok::<_, std::io::Error>(())
+        })?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn splice_tee_and_copy_through_kernel_pipes() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::net::{Shutdown, TcpListener, TcpStream};
+
+    async fn recv_exact(stream: &Handle<TcpStream>, len: usize) -> std::io::Result<Vec<u8>> {
+        let mut buf = vec![0; len];
+        let mut received = 0;
+        while received < len {
+            match stream.recv(&mut buf[received..]).await? {
+                0 => break,
+                n => received += n,
+            }
+        }
+        buf.truncate(received);
+        Ok(buf)
+    }
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+
+        drive(async {
+            let listener = Handle::<TcpListener>::bind("127.0.0.1:0")?;
+            let addr = listener.get_ref().local_addr()?;
+            let left = Handle::<TcpStream>::connect(addr).await?;
+            let (left_peer, _) = listener.accept().await?;
+            let right = Handle::<TcpStream>::connect(addr).await?;
+            let (right_peer, _) = listener.accept().await?;
+
+            // Socket to socket.
+            left.send(b"quark-gluon").await?;
+            let n = nuclei::io::splice(&left_peer, &right, 64).await?;
+            assert_eq!(recv_exact(&right_peer, n).await?, &b"quark-gluon"[..n]);
+
+            // File to socket, from an offset.
+            let expected = std::fs::read("Cargo.toml")?;
+            let file = Handle::<File>::open("Cargo.toml").await?;
+            let len = expected.len() - 10;
+            let (sent, received) = futures::join!(
+                nuclei::io::sendfile(&file, &right, 10, len + 10),
+                recv_exact(&right_peer, len)
+            );
+            assert_eq!(sent?, len);
+            assert_eq!(received?, &expected[10..]);
+
+            // Pipe to pipe, the input keeps its bytes.
+            let (reader, mut writer) = std::io::pipe()?;
+            let (mut tee_reader, tee_writer) = std::io::pipe()?;
+            writer.write_all(b"quark-gluon")?;
+            let (from, to) = (Handle::new(reader)?, Handle::new(tee_writer)?);
+            assert_eq!(nuclei::io::tee(&from, &to, 64).await?, 11);
+            let mut buf = [0; 11];
+            tee_reader.read_exact(&mut buf)?;
+            assert_eq!(&buf, b"quark-gluon");
+            from.get_ref().read_exact(&mut buf)?;
+            assert_eq!(&buf, b"quark-gluon");
+
+            // Both directions, until both ends are shut down.
+            let peers = async {
+                left.send(b"ping").await?;
+                assert_eq!(recv_exact(&right_peer, 4).await?, b"ping");
+                right_peer.send(b"pong!").await?;
+                assert_eq!(recv_exact(&left, 5).await?, b"pong!");
+
+                left.get_ref().shutdown(Shutdown::Write)?;
+                right_peer.get_ref().shutdown(Shutdown::Write)?;
+                assert_eq!(recv_exact(&left, 1).await?, b"");
+                assert_eq!(recv_exact(&right_peer, 1).await?, b"");
+                Ok::<_, std::io::Error>(())
+            };
+            let (copied, peers) =
+                futures::join!(nuclei::io::copy_bidirectional(&left_peer, &right), peers);
+            peers?;
+            assert_eq!(copied?, (4, 5));
+
+            Ok::<_, std::io::Error>(())
+        })?;
+    }
+
+    Ok(())
+}
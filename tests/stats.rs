@@ -0,0 +1,204 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn stats_track_operations() -> std::io::Result<()> {
+    use futures::FutureExt;
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::os::unix::net::UnixStream;
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+        let (l, r) = UnixStream::pair()?;
+        r.set_nonblocking(true)?;
+        let (l, r) = (Handle::new(l)?, Handle::new(r)?);
+
+        drive(async {
+            let mut buf = [0; 6];
+            let mut recv = Box::pin(r.recv(&mut buf));
+            assert!((&mut recv).now_or_never().is_none());
+
+            let stats = proactor.stats();
+            assert_eq!(stats.inflight, 1, "{:?}", backend);
+            assert!(stats.sqes_submitted >= 1);
+            if backend == IoBackend::Epoll {
+                assert_eq!(stats.registered_fds, 1);
+                assert_eq!(stats.completions, 1);
+            }
+
+            l.send(b"nuclei").await?;
+            assert_eq!(recv.await?, 6);
+
+            // Waker firing on another thread wakes the driving thread up from the wait.
+            let (tx, rx) = futures::channel::oneshot::channel();
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                let _ = tx.send(());
+            });
+            rx.await.unwrap();
+            Ok::<_, std::io::Error>(())
+        })?;
+
+        let stats = proactor.stats();
+        assert_eq!(stats.inflight, 0, "{:?}", backend);
+        assert!(stats.cqes_reaped >= 1);
+        assert!(stats.wakeups >= 1);
+        assert_eq!(stats.cq_overflow, 0);
+        assert_eq!(stats.completions, 0);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn latency_per_op_class() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+        let (l, r) = UnixStream::pair()?;
+        r.set_nonblocking(true)?;
+        let (l, r) = (Handle::new(l)?, Handle::new(r)?);
+
+        drive(async {
+            let writer = async {
+                nuclei::time::sleep(Duration::from_millis(10)).await?;
+                l.send(b"nuclei").await
+            };
+            let mut buf = [0; 6];
+            let (sent, received) = futures::join!(writer, r.recv(&mut buf));
+            assert_eq!(sent?, 6);
+            assert_eq!(received?, 6);
+            Ok::<_, std::io::Error>(())
+        })?;
+
+        let recv = proactor.latency(OpClass::Recv);
+        assert_eq!(recv.count(), 1, "{:?}", backend);
+        assert!(recv.mean().unwrap() >= Duration::from_millis(5));
+        assert!(recv.quantile(0.99).unwrap() >= recv.mean().unwrap());
+        assert_eq!(recv.buckets().map(|(_, n)| n).sum::<u64>(), 1);
+
+        let statx = proactor.latency(OpClass::Statx);
+        assert_eq!(statx.count(), 0);
+        assert_eq!(statx.quantile(0.5), None);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn event_hook_sees_lifecycle() -> std::io::Result<()> {
+    use futures::FutureExt;
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::UnixStream;
+    use std::sync::{Arc, Mutex};
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let hook = {
+            let events = events.clone();
+            move |event: &OpEvent| events.lock().unwrap().push(*event)
+        };
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            event_hook: Some(Arc::new(hook)),
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+        let (l, r) = UnixStream::pair()?;
+        r.set_nonblocking(true)?;
+        let fd = r.as_raw_fd();
+        let (l, r) = (Handle::new(l)?, Handle::new(r)?);
+
+        drive(async {
+            let mut buf = [0; 6];
+            let mut recv = Box::pin(r.recv(&mut buf));
+            assert!((&mut recv).now_or_never().is_none());
+            l.send(b"nuclei").await?;
+            assert_eq!(recv.await?, 6);
+            Ok::<_, std::io::Error>(())
+        })?;
+
+        let events = events.lock().unwrap();
+        let recv: Vec<_> = events
+            .iter()
+            .filter(|e| e.class == Some(OpClass::Recv))
+            .collect();
+        assert_eq!(recv.len(), 2, "{:?}: {:?}", backend, recv);
+        assert_eq!(recv[0].kind, OpEventKind::Submit);
+        assert_eq!(recv[1].kind, OpEventKind::Complete);
+        assert_eq!(recv[0].user_data, recv[1].user_data);
+        assert!(recv.iter().all(|e| e.fd == fd));
+        if backend == IoBackend::IoUring {
+            assert!(recv[0].opcode.is_some());
+            assert_eq!(recv[1].result, 6);
+            assert_eq!(recv[1].bytes, 6);
+        } else {
+            assert_eq!(recv[1].bytes, 0);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn inflight_dump() -> std::io::Result<()> {
+    use futures::FutureExt;
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::UnixStream;
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+        let (l, r) = UnixStream::pair()?;
+        r.set_nonblocking(true)?;
+        let fd = r.as_raw_fd();
+        let (l, r) = (Handle::new(l)?, Handle::new(r)?);
+
+        drive(async {
+            let mut buf = [0; 6];
+            let mut recv = Box::pin(r.recv(&mut buf));
+            assert!((&mut recv).now_or_never().is_none());
+
+            let inflight = proactor.inflight();
+            assert_eq!(inflight.len(), 1, "{:?}: {:?}", backend, inflight);
+            assert_eq!(inflight[0].fd, fd);
+            assert_eq!(inflight[0].class, Some(OpClass::Recv));
+            assert_eq!(inflight[0].opcode.is_some(), backend == IoBackend::IoUring);
+            assert!(!inflight[0].cancelled);
+            assert!(format!("{:?}", proactor).contains("Recv"));
+
+            l.send(b"nuclei").await?;
+            assert_eq!(recv.await?, 6);
+            Ok::<_, std::io::Error>(())
+        })?;
+
+        assert!(proactor.inflight().is_empty());
+    }
+
+    Ok(())
+}
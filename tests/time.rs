@@ -0,0 +1,52 @@
+#[cfg(target_os = "linux")]
+#[test]
+fn sleep_elapses() -> std::io::Result<()> {
+    use nuclei::*;
+    use std::time::{Duration, Instant};
+
+    drive(async {
+        let started = Instant::now();
+        time::sleep(Duration::from_millis(50)).await?;
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        Ok(())
+    })
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn interval_ticks_periodically() -> std::io::Result<()> {
+    use nuclei::*;
+    use std::time::{Duration, Instant};
+
+    drive(async {
+        let started = Instant::now();
+        let mut interval = time::interval(Duration::from_millis(20))?;
+        for _ in 0..3 {
+            interval.tick().await?;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(60));
+
+        Ok(())
+    })
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn timeout_elapses_on_pending_future() -> std::io::Result<()> {
+    use nuclei::*;
+    use std::io::ErrorKind;
+    use std::time::Duration;
+
+    drive(async {
+        let err = time::timeout(Duration::from_millis(20), futures::future::pending::<()>())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+
+        let done = time::timeout(Duration::from_secs(5), async { 42 }).await?;
+        assert_eq!(done, 42);
+
+        Ok(())
+    })
+}
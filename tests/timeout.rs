@@ -0,0 +1,107 @@
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn recv_times_out_on_silent_peer() -> std::io::Result<()> {
+    use futures::AsyncReadExt;
+    use nuclei::*;
+    use std::io::{ErrorKind, Write};
+    use std::os::unix::net::UnixStream;
+    use std::time::{Duration, Instant};
+
+    let (local, mut remote) = UnixStream::pair()?;
+
+    drive(async {
+        let mut stream = Handle::<UnixStream>::new(local)?;
+        stream.set_read_timeout(Some(Duration::from_millis(100)));
+        assert_eq!(stream.read_timeout(), Some(Duration::from_millis(100)));
+
+        let mut buf = [0_u8; 4];
+        let started = Instant::now();
+        let err = stream.recv(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert!(started.elapsed() >= Duration::from_millis(100));
+
+        // Timed out receive is cancelled, data is left for the next one.
+        remote.write_all(b"ping")?;
+        stream.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"ping");
+
+        Ok(())
+    })
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn recv_from_times_out_on_silent_peer() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::io::ErrorKind;
+    use std::net::UdpSocket;
+    use std::time::{Duration, Instant};
+
+    for backend in [IoBackend::IoUring, IoBackend::Epoll] {
+        let proactor = Proactor::new(NucleiConfig {
+            backend: Some(backend),
+            ..NucleiConfig::default()
+        })?;
+        let _guard = proactor.enter();
+
+        drive(async {
+            let remote = UdpSocket::bind("127.0.0.1:0")?;
+            let local = UdpSocket::bind("127.0.0.1:0")?;
+            local.set_nonblocking(true)?;
+            let mut local = Handle::new(local)?;
+            local.set_read_timeout(Some(Duration::from_millis(100)));
+
+            let mut buf = [0_u8; 4];
+            let started = Instant::now();
+            let err = local.recv_from(&mut buf).await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::TimedOut, "{:?}: {}", backend, err);
+            assert!(started.elapsed() >= Duration::from_millis(100));
+
+            // Timed out receive is cancelled, the datagram is left for the next one.
+            remote.send_to(b"ping", local.get_ref().local_addr()?)?;
+            let (n, from) = local.recv_from(&mut buf).await?;
+            assert_eq!((&buf[..n], from), (&b"ping"[..], remote.local_addr()?));
+
+            Ok::<_, std::io::Error>(())
+        })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "iouring")]
+#[cfg(target_os = "linux")]
+#[test]
+fn dropped_recv_with_timeout_is_deregistered() -> std::io::Result<()> {
+    use nuclei::config::{IoBackend, NucleiConfig};
+    use nuclei::*;
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    let proactor = Proactor::new(NucleiConfig {
+        backend: Some(IoBackend::Epoll),
+        ..NucleiConfig::default()
+    })?;
+    let _guard = proactor.enter();
+    let (local, _remote) = UnixStream::pair()?;
+    local.set_nonblocking(true)?;
+
+    drive(async {
+        let mut stream = Handle::<UnixStream>::new(local)?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)));
+
+        // Interest and its deadline are dropped along with the receive.
+        for _ in 0..16 {
+            let mut buf = [0_u8; 4];
+            let recv = stream.recv(&mut buf);
+            futures::pin_mut!(recv);
+            assert!(futures::poll!(recv).is_pending());
+        }
+        assert!(proactor.inflight().is_empty(), "{:?}", proactor.inflight());
+
+        Ok(())
+    })
+}